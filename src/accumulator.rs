@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+
+use crate::{result_to_stat, Entry, Line, Result, Stats, StatsOptions};
+
+/// A running mean and sum of squared deviations (M2), updated one value at a
+/// time with Welford's algorithm. Unlike keeping a running sum of squares,
+/// this doesn't lose precision when the variance is small relative to the
+/// mean.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Welford {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Welford {
+    pub fn push(&mut self, value: f32) {
+        let value = f64::from(value);
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// The mean so far; NaN before any values.
+    pub fn mean(&self) -> f32 {
+        match self.count {
+            0 => f32::NAN,
+            _ => self.mean as f32,
+        }
+    }
+
+    /// The sample variance so far, agreeing with `variance`: NaN before any
+    /// values and 0 after one.
+    pub fn variance(&self) -> f32 {
+        match self.count {
+            0 => f32::NAN,
+            1 => 0.0,
+            n => (self.m2 / (n - 1) as f64) as f32,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Running {
+    result: Result,
+    moments: Welford,
+    /// Runs that have logged only one of their time and their success so
+    /// far, keyed by path.
+    unmatched: BTreeMap<String, (Option<f32>, bool)>,
+}
+
+/// Stats that are kept up to date one line at a time, for following a log
+/// as it grows instead of re-reading it.
+///
+/// Counts, the mean, and the spread (standard deviation and standard error)
+/// come from running totals. The median and percentiles can't be maintained
+/// exactly that way, so every run time is still retained and `snapshot`
+/// computes only those (and any `--ci-method` interval) from the samples;
+/// bounding memory would mean swapping in a quantile sketch and accepting an
+/// approximate median.
+#[derive(Debug)]
+pub struct Accumulator {
+    configs: BTreeMap<(u32, u32), Running>,
+    options: StatsOptions,
+    base_seed: u64,
+}
+
+impl Accumulator {
+    pub fn new(options: &StatsOptions) -> Self {
+        Self {
+            configs: BTreeMap::new(),
+            options: *options,
+            base_seed: options.base_seed(),
+        }
+    }
+
+    pub fn push(&mut self, line: &Line) {
+        let running = self.configs.entry((line.population_size, line.num_generations)).or_default();
+        let run = running.unmatched.entry(line.path.to_owned()).or_default();
+        match line.entry {
+            Entry::Success(details) => {
+                running.result.num_successes += 1;
+                running.result.final_fitnesses.extend(details.map(|d| d.fitness));
+                run.1 = true;
+            }
+            Entry::RunTime(value, unit) => {
+                running.result.num_runs += 1;
+                running.result.run_times.push(value);
+                running.result.units_normalized |= unit.is_some();
+                running.moments.push(value);
+                run.0 = Some(value);
+            }
+        }
+        if let &mut (Some(run_time), true) = run {
+            running.result.success_times.push(run_time);
+            running.unmatched.remove(line.path);
+        }
+    }
+
+    /// The stats as of the lines pushed so far; the same as running
+    /// `data_to_stats` over all of them at once, up to rounding in the mean
+    /// and spread.
+    pub fn snapshot(&self) -> Stats {
+        self.configs
+            .iter()
+            .map(|(key, running)| {
+                let mut result = running.result.clone();
+                // Matched runs were removed, so every unmatched one with a time
+                // hasn't succeeded (yet).
+                result.timed_no_success = running.unmatched.values().filter(|(run_time, _)| run_time.is_some()).count();
+                let (mean, variance) = (running.moments.mean(), running.moments.variance());
+                (*key, result_to_stat(*key, result, mean, variance, &self.options, self.base_seed))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{approx_eq, data_to_stats, parse_lines, Data, MedianMethod, ParseOptions, DEFAULT_ABSOLUTE_ERROR};
+
+    const RUNS: &str = "\
+PS_100/NG_50/run_1.output: 4.5
+PS_100/NG_50/run_2.output: 6.0
+PS_100/NG_50/run_1.output:SUCCESS
+PS_200/NG_50/run_1.output: 3.25
+PS_100/NG_50/run_3.output: 5.0
+PS_200/NG_50/run_2.output:SUCCESS 27 0.5
+PS_200/NG_50/run_2.output: 1.0
+";
+
+    fn options() -> StatsOptions {
+        StatsOptions {
+            confidence: 0.95,
+            ci_method: None,
+            resamples: 1000,
+            seed: Some(0),
+            median_method: MedianMethod::Average,
+            risk_lambda: 1.0,
+        }
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a.is_nan() && b.is_nan()) || approx_eq(a, b, 1e-5, DEFAULT_ABSOLUTE_ERROR)
+    }
+
+    #[test]
+    fn each_snapshot_matches_the_batch_stats_so_far() {
+        let (lines, _) = parse_lines(RUNS, &ParseOptions::default()).unwrap();
+        let mut accumulator = Accumulator::new(&options());
+        for pushed in 1..=lines.len() {
+            accumulator.push(&lines[pushed - 1]);
+            let snapshot = accumulator.snapshot();
+            let batch = data_to_stats(lines[..pushed].iter().collect::<Data>(), &options());
+            assert_eq!(snapshot.keys().collect::<Vec<_>>(), batch.keys().collect::<Vec<_>>());
+            for (key, expected) in &batch {
+                let actual = &snapshot[key];
+                assert_eq!(actual.result, expected.result, "{key:?} after {pushed} lines");
+                for (a, b) in [
+                    (actual.mean_run_time, expected.mean_run_time),
+                    (actual.median_run_time, expected.median_run_time),
+                    (actual.std_dev_run_time, expected.std_dev_run_time),
+                    (actual.sem_run_time, expected.sem_run_time),
+                    (actual.risk_adjusted_time, expected.risk_adjusted_time),
+                ] {
+                    assert!(close(a, b), "{key:?} after {pushed} lines: {a} != {b}");
+                }
+                assert_eq!(actual.success_rate, expected.success_rate);
+                assert_eq!(actual.mean_final_fitness, expected.mean_final_fitness);
+            }
+        }
+    }
+
+    #[test]
+    fn welford_agrees_with_the_two_pass_variance() {
+        let samples: [&[f32]; 3] = [
+            &[4.5, 6.0, 1.25, 9.0, 3.5, 3.5, 7.75],
+            // A small spread around a large mean, where a running sum of
+            // squares would lose it.
+            &[10_000.1, 10_000.3, 10_000.2, 10_000.4],
+            &[2.0],
+        ];
+        for vals in samples {
+            let mut welford = Welford::default();
+            for &v in vals {
+                welford.push(v);
+            }
+            assert!(approx_eq(welford.mean(), crate::mean(vals), 1e-6, DEFAULT_ABSOLUTE_ERROR));
+            assert!(
+                approx_eq(welford.variance(), crate::variance(vals), 1e-3, 1e-6),
+                "{vals:?}: {} != {}",
+                welford.variance(),
+                crate::variance(vals)
+            );
+        }
+        assert!(Welford::default().mean().is_nan());
+        assert!(Welford::default().variance().is_nan());
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::Context;
+use tracing::{debug, info};
+
+use crate::{sorted_entries, Stat, Stats};
+
+/// A key identifying a set of inputs, built from each file's path,
+/// modification time, and size along with `settings` (everything else that
+/// changes the computed stats). Any change to a file gives a new key, so
+/// stale entries are simply never looked up again. Returns `None` for stdin,
+/// which can't be cached.
+///
+/// This uses the standard library's hasher, which isn't guaranteed to be
+/// stable across Rust releases; at worst an upgrade means a cold cache.
+pub fn key(paths: &[PathBuf], settings: &str) -> anyhow::Result<Option<String>> {
+    let mut hasher = DefaultHasher::new();
+    for path in paths {
+        if path == Path::new("-") {
+            return Ok(None);
+        }
+        let metadata = fs::metadata(path).with_context(|| format!("Couldn't read metadata for {}", path.display()))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_nanos());
+        fs::canonicalize(path).unwrap_or_else(|_| path.clone()).hash(&mut hasher);
+        modified.hash(&mut hasher);
+        metadata.len().hash(&mut hasher);
+    }
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    settings.hash(&mut hasher);
+    Ok(Some(format!("{:016x}", hasher.finish())))
+}
+
+/// Entries are bincode rather than JSON, which has no way to write the NaN
+/// and infinite stats of e.g. a configuration without any run times.
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{key}.bin"))
+}
+
+/// The cached stats for `key` and the number of lines parsed to compute
+/// them, if there are any. An unreadable or outdated cache file is treated as
+/// a miss.
+pub fn load(dir: &Path, key: &str) -> Option<(Stats, usize)> {
+    let path = entry_path(dir, key);
+    let bytes = fs::read(&path).ok()?;
+    match bincode::deserialize::<(usize, Vec<((u32, u32), Stat)>)>(&bytes) {
+        Ok((lines_parsed, entries)) => {
+            info!(path = %path.display(), "using cached stats");
+            Some((entries.into_iter().collect(), lines_parsed))
+        }
+        Err(error) => {
+            debug!(path = %path.display(), %error, "ignoring unreadable cache entry");
+            None
+        }
+    }
+}
+
+pub fn store(dir: &Path, key: &str, stats: &Stats, lines_parsed: usize) -> anyhow::Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Couldn't create cache directory {}", dir.display()))?;
+    let path = entry_path(dir, key);
+    let bytes = bincode::serialize(&(lines_parsed, sorted_entries(stats)))?;
+    fs::write(&path, bytes).with_context(|| format!("Couldn't write cache entry {}", path.display()))?;
+    debug!(path = %path.display(), "stored stats in cache");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::{data_to_stats, Data, MedianMethod, Result, StatsOptions};
+
+    #[test]
+    fn stored_stats_load_back_including_nan() {
+        let data = Data::from([
+            ((100, 50), Result { num_runs: 2, num_successes: 1, run_times: vec![1.5, 2.5], ..Result::default() }),
+            ((200, 50), Result { num_successes: 1, ..Result::default() }),
+        ]);
+        let options = StatsOptions {
+            confidence: 0.95,
+            ci_method: None,
+            resamples: 1000,
+            seed: Some(0),
+            median_method: MedianMethod::Average,
+            risk_lambda: 1.0,
+        };
+        let stats = data_to_stats(data, &options);
+        assert!(stats[&(200, 50)].mean_run_time.is_nan());
+
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load(dir.path(), "0123").is_none());
+        store(dir.path(), "0123", &stats, 7).unwrap();
+        let (loaded, lines_parsed) = load(dir.path(), "0123").unwrap();
+        assert_eq!(lines_parsed, 7);
+        assert_eq!(loaded.keys().collect::<Vec<_>>(), stats.keys().collect::<Vec<_>>());
+        assert_eq!(loaded[&(100, 50)].result, stats[&(100, 50)].result);
+        assert_eq!(loaded[&(100, 50)].mean_run_time, 2.0);
+        assert!(loaded[&(200, 50)].mean_run_time.is_nan());
+        assert_eq!(loaded[&(200, 50)].success_rate, None);
+    }
+
+    #[test]
+    fn the_key_changes_with_the_input_and_settings() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"PS_100/NG_50/run_1.output: 1.5\n").unwrap();
+        let paths = [file.path().to_path_buf()];
+        let before = key(&paths, "settings").unwrap().unwrap();
+        assert_eq!(key(&paths, "settings").unwrap().unwrap(), before);
+        assert_ne!(key(&paths, "other settings").unwrap().unwrap(), before);
+
+        file.write_all(b"PS_100/NG_50/run_2.output: 2.5\n").unwrap();
+        assert_ne!(key(&paths, "settings").unwrap().unwrap(), before);
+
+        assert_eq!(key(&[PathBuf::from("-")], "settings").unwrap(), None);
+    }
+}
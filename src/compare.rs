@@ -0,0 +1,238 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    approx_eq, mean,
+    output::{Better, Column},
+    Stats,
+};
+
+/// How close two values must be to count as the same, i.e. unchanged
+/// between two datasets or tied in a ranking. See `approx_eq`.
+#[derive(Debug, Clone, Copy)]
+pub struct Tolerance {
+    /// A fraction of the larger of the two values.
+    pub relative: f32,
+    pub absolute: f32,
+}
+
+impl Tolerance {
+    fn is_negligible(self, a: f32, b: f32) -> bool {
+        approx_eq(a, b, self.relative, self.absolute)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Changed,
+    Unchanged,
+    OnlyInFirst,
+    OnlyInSecond,
+}
+
+impl Status {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Changed => "changed",
+            Self::Unchanged => "unchanged",
+            Self::OnlyInFirst => "only in first",
+            Self::OnlyInSecond => "only in second",
+        }
+    }
+
+    /// Whether `--diff-only` shows this configuration: it changed or is only
+    /// in one of the datasets.
+    pub fn is_difference(self) -> bool {
+        self != Self::Unchanged
+    }
+}
+
+/// How one configuration's metric moved between two datasets.
+#[derive(Debug, Clone, Copy)]
+pub struct Comparison {
+    pub first: Option<f32>,
+    pub second: Option<f32>,
+    /// `second - first`, or `None` when it's within epsilon, the
+    /// configuration is missing from one side, or the metric is missing on
+    /// one side.
+    pub delta: Option<f32>,
+    pub status: Status,
+}
+
+/// Compares `metric` across every configuration in either dataset, in key
+/// order. A configuration in both datasets is unchanged when the values are
+/// within `tolerance` or the metric is missing (or NaN) on both sides, and
+/// changed when only one side has a value.
+pub fn compare(first: &Stats, second: &Stats, metric: &Column, tolerance: Tolerance) -> Vec<((u32, u32), Comparison)> {
+    let keys = first.keys().chain(second.keys()).collect::<BTreeSet<_>>();
+    // `None` for a configuration the dataset doesn't have, `Some(None)` when
+    // it has the configuration but not the metric.
+    let value = |stats: &Stats, key| stats.get(key).map(|s| metric.value(key, s).as_f32());
+    let number = |value: Option<Option<f32>>| value.map(|v| v.filter(|v| !v.is_nan()));
+    keys.into_iter()
+        .map(|key| {
+            let (first, second) = (value(first, key), value(second, key));
+            let (delta, status) = match (number(first), number(second)) {
+                (Some(Some(a)), Some(Some(b))) if tolerance.is_negligible(a, b) => (None, Status::Unchanged),
+                (Some(Some(a)), Some(Some(b))) => (Some(b - a), Status::Changed),
+                (Some(None), Some(None)) => (None, Status::Unchanged),
+                (Some(_), Some(_)) => (None, Status::Changed),
+                (Some(_), None) => (None, Status::OnlyInFirst),
+                (None, _) => (None, Status::OnlyInSecond),
+            };
+            let comparison = Comparison {
+                first: first.flatten(),
+                second: second.flatten(),
+                delta,
+                status,
+            };
+            (*key, comparison)
+        })
+        .collect()
+}
+
+/// A configuration and each dataset's rank for it, in dataset order.
+pub type ConfigRanks = ((u32, u32), Vec<f32>);
+
+/// Each dataset's rank on `metric`, for every configuration that all of the
+/// datasets have a value for, in key order. Rank 1 is the best according to
+/// `metric.better`, and datasets within `tolerance` of each other share the
+/// average of the ranks they span. `None` if `metric` has no better direction.
+pub fn rank(datasets: &[Stats], metric: &Column, tolerance: Tolerance) -> Option<Vec<ConfigRanks>> {
+    let better = metric.better?;
+    let Some(first) = datasets.first() else {
+        return Some(Vec::new());
+    };
+    let ranks = first
+        .keys()
+        .filter_map(|key| {
+            let values = datasets
+                .iter()
+                .map(|stats| stats.get(key).and_then(|s| metric.value(key, s).as_f32()).filter(|v| !v.is_nan()))
+                .collect::<Option<Vec<_>>>()?;
+            Some((*key, ranks_of(&values, better, tolerance)))
+        })
+        .collect();
+    Some(ranks)
+}
+
+fn ranks_of(values: &[f32], better: Better, tolerance: Tolerance) -> Vec<f32> {
+    values
+        .iter()
+        .map(|&value| {
+            let tied = values.iter().filter(|&&other| tolerance.is_negligible(other, value)).count();
+            let ahead = values
+                .iter()
+                .filter(|&&other| !tolerance.is_negligible(other, value))
+                .filter(|&&other| match better {
+                    Better::Lower => other < value,
+                    Better::Higher => other > value,
+                })
+                .count();
+            ahead as f32 + (tied as f32 + 1.0) / 2.0
+        })
+        .collect()
+}
+
+/// Each dataset's mean rank across the configurations in `ranks`, as in the
+/// Friedman test; lower is better overall. NaN when there are no
+/// configurations to rank.
+pub fn average_ranks(ranks: &[ConfigRanks], num_datasets: usize) -> Vec<f32> {
+    (0..num_datasets)
+        .map(|i| mean(&ranks.iter().map(|(_, r)| r[i]).collect::<Vec<_>>()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data_to_stats, output::parse_column, MedianMethod, Result, StatsOptions};
+
+    /// The `compare` subcommand's default tolerance.
+    const DEFAULT: Tolerance = Tolerance {
+        relative: crate::DEFAULT_RELATIVE_ERROR,
+        absolute: 1e-3,
+    };
+
+    fn stats(configs: &[((u32, u32), &[f32])]) -> Stats {
+        let data = configs
+            .iter()
+            .map(|&(key, run_times)| {
+                let result = Result {
+                    num_runs: run_times.len(),
+                    num_successes: run_times.len(),
+                    run_times: run_times.to_vec(),
+                    ..Result::default()
+                };
+                (key, result)
+            })
+            .collect();
+        let options = StatsOptions {
+            confidence: 0.95,
+            ci_method: None,
+            resamples: 1000,
+            seed: Some(0),
+            median_method: MedianMethod::Average,
+            risk_lambda: 1.0,
+        };
+        data_to_stats(data, &options)
+    }
+
+    #[test]
+    fn deltas_within_epsilon_are_unchanged() {
+        let first = stats(&[((100, 50), &[10.0]), ((200, 50), &[10.0]), ((300, 50), &[1.0])]);
+        let second = stats(&[((100, 50), &[10.0001]), ((200, 50), &[15.0]), ((400, 50), &[1.0])]);
+        let comparisons = compare(&first, &second, parse_column("mean_run_time").unwrap(), DEFAULT);
+        let statuses = comparisons.iter().map(|&(key, c)| (key, c.status, c.delta)).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                ((100, 50), Status::Unchanged, None),
+                ((200, 50), Status::Changed, Some(5.0)),
+                ((300, 50), Status::OnlyInFirst, None),
+                ((400, 50), Status::OnlyInSecond, None),
+            ]
+        );
+
+        // Without --ci-method, mean_ci_low is missing on both sides.
+        let comparisons = compare(&first, &second, parse_column("mean_ci_low").unwrap(), DEFAULT);
+        assert_eq!(comparisons[0].1.status, Status::Unchanged);
+        assert_eq!(comparisons[1].1.status, Status::Unchanged);
+    }
+
+    #[test]
+    fn three_datasets_are_ranked_per_configuration_and_on_average() {
+        let datasets = [
+            stats(&[((100, 50), &[1.0]), ((200, 50), &[5.0]), ((300, 50), &[1.0])]),
+            stats(&[((100, 50), &[2.0]), ((200, 50), &[5.0]), ((300, 50), &[2.0])]),
+            stats(&[((100, 50), &[3.0]), ((200, 50), &[1.0])]),
+        ];
+        let metric = parse_column("mean_run_time").unwrap();
+        let ranks = rank(&datasets, metric, DEFAULT).unwrap();
+        // 300:50 isn't in every dataset, and the tied 5s share ranks 2 and 3.
+        assert_eq!(ranks, [((100, 50), vec![1.0, 2.0, 3.0]), ((200, 50), vec![2.5, 2.5, 1.0])]);
+        assert_eq!(average_ranks(&ranks, datasets.len()), [1.75, 2.25, 2.0]);
+
+        // Higher is better for success_rate, and every dataset ties.
+        let ranks = rank(&datasets, parse_column("success_rate").unwrap(), DEFAULT).unwrap();
+        assert_eq!(ranks[0].1, [2.0, 2.0, 2.0]);
+
+        assert!(rank(&datasets, parse_column("num_runs").unwrap(), DEFAULT).is_none());
+    }
+
+    #[test]
+    fn diff_only_keeps_the_changed_and_one_sided_configurations() {
+        let first = stats(&[((100, 50), &[1.0]), ((200, 50), &[2.0]), ((300, 50), &[3.0]), ((400, 50), &[4.0])]);
+        let second = stats(&[((100, 50), &[1.0]), ((200, 50), &[2.0]), ((300, 50), &[9.0]), ((500, 50), &[5.0])]);
+        let differences = |metric| {
+            compare(&first, &second, parse_column(metric).unwrap(), DEFAULT)
+                .into_iter()
+                .filter(|(_, c)| c.status.is_difference())
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(differences("mean_run_time"), [(300, 50), (400, 50), (500, 50)]);
+        // mean_ci_low is missing everywhere, so only the one-sided
+        // configurations differ.
+        assert_eq!(differences("mean_ci_low"), [(400, 50), (500, 50)]);
+    }
+}
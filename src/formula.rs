@@ -0,0 +1,148 @@
+//! User-defined derived metrics for `--metric-formula`: an arithmetic
+//! expression over the output columns, evaluated once per configuration.
+
+use evalexpr::{build_operator_tree, ContextWithMutableVariables, DefaultNumericTypes, HashMapContext, Node};
+
+use crate::{
+    output::{Column, COLUMNS},
+    Stats,
+};
+
+/// The name `formula` is reported under; it can't appear in a formula itself.
+pub const FORMULA_COLUMN: &str = "formula";
+
+/// A parsed `--metric-formula` expression.
+#[derive(Debug, Clone)]
+pub struct Formula {
+    source: String,
+    tree: Node<DefaultNumericTypes>,
+}
+
+impl Formula {
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// The columns a formula can refer to.
+fn variables() -> impl Iterator<Item = &'static Column> {
+    COLUMNS.iter().filter(|c| c.name != FORMULA_COLUMN)
+}
+
+/// Parses `s`, e.g. `num_successes / (mean_run_time * num_gens)`, checking
+/// that every identifier in it is a column name.
+pub fn parse_formula(s: &str) -> Result<Formula, String> {
+    let tree = build_operator_tree::<DefaultNumericTypes>(s).map_err(|e| format!("invalid formula `{s}`: {e}"))?;
+    if let Some(unknown) = tree.iter_variable_identifiers().find(|&name| variables().all(|c| c.name != name)) {
+        let names = variables().map(|c| c.name).collect::<Vec<_>>();
+        return Err(format!("unknown field `{unknown}` in formula; expected one of {}", names.join(", ")));
+    }
+    Ok(Formula { source: s.to_string(), tree })
+}
+
+/// Fills in `formula` for every configuration with the value of `formula`.
+/// Every column is a float, so `/` never rounds; missing values are NaN. A
+/// result that isn't a finite number is left missing.
+pub fn apply_formula(stats: &mut Stats, formula: &Formula) {
+    for (key, s) in stats.iter_mut() {
+        let mut context = HashMapContext::<DefaultNumericTypes>::new();
+        for column in variables() {
+            let value = column.value(key, s).as_f32().map_or(f64::NAN, f64::from);
+            context
+                .set_value(column.name.to_string(), evalexpr::Value::Float(value))
+                .expect("a HashMapContext accepts any variable");
+        }
+        s.formula = formula
+            .tree
+            .eval_number_with_context(&context)
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(|v| v as f32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_to_stats,
+        output::{parse_column, write_stats, Format, KeyOrder, OutputOptions},
+        Data, MedianMethod, Result, StatsOptions,
+    };
+
+    fn stats() -> Stats {
+        let result = |run_times: &[f32], num_successes| Result {
+            num_runs: run_times.len(),
+            num_successes,
+            run_times: run_times.to_vec(),
+            ..Result::default()
+        };
+        let data = Data::from([
+            ((100, 50), result(&[4.0, 4.0], 2)),
+            ((100, 100), result(&[2.0, 4.0], 1)),
+            ((200, 50), result(&[1.0], 1)),
+            ((200, 100), result(&[], 0)),
+        ]);
+        let options = StatsOptions {
+            confidence: 0.95,
+            ci_method: None,
+            resamples: 1000,
+            seed: Some(0),
+            median_method: MedianMethod::Average,
+            risk_lambda: 1.0,
+        };
+        data_to_stats(data, &options)
+    }
+
+    #[test]
+    fn a_formula_gives_a_sortable_value_per_configuration() {
+        let formula = parse_formula("num_successes / mean_run_time").unwrap();
+        assert_eq!(formula.source(), "num_successes / mean_run_time");
+        let mut stats = stats();
+        apply_formula(&mut stats, &formula);
+        let values = stats.iter().map(|(&key, s)| (key, s.formula)).collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [((100, 50), Some(0.5)), ((100, 100), Some(1.0 / 3.0)), ((200, 50), Some(1.0)), ((200, 100), None)]
+        );
+
+        let options = OutputOptions {
+            format: Format::Csv,
+            include_header: true,
+            na_string: "NA".to_string(),
+            key_order: KeyOrder::PopGens,
+            sort_by: Some(parse_column(FORMULA_COLUMN).unwrap()),
+            sort_stable: true,
+            grid_metric: parse_column("mean_run_time").unwrap(),
+            pop_values: None,
+            gens_values: None,
+            include_raw: false,
+            interpolate: false,
+            compact: false,
+        };
+        let sorted_keys = |source: &str| {
+            let mut sorted = self::stats();
+            apply_formula(&mut sorted, &parse_formula(source).unwrap());
+            let mut out = Vec::new();
+            write_stats(&mut out, &sorted, 0, &options).unwrap();
+            let csv = String::from_utf8(out).unwrap();
+            csv.lines().skip(1).map(|line| line.splitn(3, ',').take(2).collect::<Vec<_>>().join(":")).collect::<Vec<_>>()
+        };
+        assert_eq!(sorted_keys(formula.source()), ["100:100", "100:50", "200:50", "200:100"]);
+        // --sort-by is always ascending, so negating the formula sorts the
+        // other way; the configuration with no value stays last either way.
+        assert_eq!(sorted_keys("-(num_successes / mean_run_time)"), ["200:50", "100:50", "100:100", "200:100"]);
+    }
+
+    #[test]
+    fn an_unknown_field_lists_the_valid_ones() {
+        let error = parse_formula("num_successes / run_time").unwrap_err();
+        assert!(error.starts_with("unknown field `run_time` in formula; expected one of pop_size, num_gens,"), "{error}");
+        let names = error.split("expected one of ").nth(1).unwrap().split(", ").collect::<Vec<_>>();
+        assert!(names.contains(&"mean_run_time"), "{error}");
+        assert!(!names.contains(&FORMULA_COLUMN), "{error}");
+
+        assert!(parse_formula(FORMULA_COLUMN).is_err());
+        assert!(parse_formula("(num_successes / mean_run_time").unwrap_err().starts_with("invalid formula"));
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use flate2::read::MultiGzDecoder;
+use memmap2::Mmap;
+use tracing::warn;
+
+/// The raw text of an input, either read into memory or memory-mapped.
+pub enum Source {
+    Text(String),
+    Mapped(Mmap),
+}
+
+impl Source {
+    /// Reads `path`, with `-` meaning stdin. With `mmap` set, files are mapped
+    /// rather than copied into a `String`; stdin can't be mapped, so it is
+    /// always read normally. With `lossy` set, lines that aren't valid UTF-8
+    /// are dropped with a warning instead of failing the whole input. gzip and
+    /// zstd input is recognized by its magic bytes and decompressed (into
+    /// memory, even with `mmap`).
+    pub fn open(path: &Path, mmap: bool, lossy: bool) -> anyhow::Result<Self> {
+        if path == Path::new("-") {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes).context("Couldn't read stdin")?;
+            return Self::from_bytes(bytes, path, lossy);
+        }
+        if mmap {
+            let file = File::open(path).with_context(|| format!("Couldn't open file {}", path.display()))?;
+            // SAFETY: The map is only read, and we assume nothing truncates the
+            // log out from under us while we're processing it.
+            let map = unsafe { Mmap::map(&file) }.with_context(|| format!("Couldn't map file {}", path.display()))?;
+            if Compression::detect(&map).is_some() {
+                return Self::from_bytes(map.to_vec(), path, lossy);
+            }
+            if lossy && std::str::from_utf8(&map).is_err() {
+                return Ok(Self::Text(decode_lossy(&map, path)));
+            }
+            return Ok(Self::Mapped(map));
+        }
+        let bytes = fs::read(path).with_context(|| format!("Couldn't open file {}", path.display()))?;
+        Self::from_bytes(bytes, path, lossy)
+    }
+
+    fn from_bytes(bytes: Vec<u8>, path: &Path, lossy: bool) -> anyhow::Result<Self> {
+        let bytes = match Compression::detect(&bytes) {
+            Some(compression) => {
+                let mut decompressed = Vec::new();
+                compression
+                    .decoder(bytes.as_slice())?
+                    .read_to_end(&mut decompressed)
+                    .with_context(|| format!("Couldn't decompress {}", path.display()))?;
+                decompressed
+            }
+            None => bytes,
+        };
+        match String::from_utf8(bytes) {
+            Ok(text) => Ok(Self::Text(text)),
+            Err(error) if lossy => Ok(Self::Text(decode_lossy(error.as_bytes(), path))),
+            Err(error) => Err(error).with_context(|| format!("{} isn't valid UTF-8 (see --lossy)", path.display())),
+        }
+    }
+
+    /// The contents as text. For a mapped file this validates the UTF-8 in
+    /// place, so every line handed to the parser is a slice of the map.
+    pub fn text(&self) -> anyhow::Result<&str> {
+        match self {
+            Self::Text(text) => Ok(text),
+            Self::Mapped(map) => std::str::from_utf8(map).context("Input isn't valid UTF-8 (see --lossy)"),
+        }
+    }
+}
+
+/// Decodes `bytes` a line at a time, dropping every line that isn't valid
+/// UTF-8 with a warning. Later lines keep their content but, since the bad
+/// ones are gone, not their line numbers.
+fn decode_lossy(bytes: &[u8], path: &Path) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for (index, line) in bytes.split_inclusive(|&b| b == b'\n').enumerate() {
+        match std::str::from_utf8(line) {
+            Ok(line) => text.push_str(line),
+            Err(error) => warn!(line_number = index + 1, %error, "skipping line of {} that isn't valid UTF-8", path.display()),
+        }
+    }
+    text
+}
+
+/// The compressed formats an input can be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The format `bytes` starts with the magic number of, if any.
+    fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    fn decoder<'a>(self, reader: impl BufRead + 'a) -> anyhow::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Self::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Self::Zstd => Box::new(zstd::Decoder::with_buffer(reader).context("Couldn't start zstd decoding")?),
+        })
+    }
+}
+
+/// A line-by-line reader over `path`, with `-` meaning stdin, decompressing
+/// gzip and zstd input on the fly.
+pub fn open_reader(path: &Path) -> anyhow::Result<Box<dyn BufRead>> {
+    let mut reader: Box<dyn BufRead> = if path == Path::new("-") {
+        Box::new(io::stdin().lock())
+    } else {
+        let file = File::open(path).with_context(|| format!("Couldn't open file {}", path.display()))?;
+        Box::new(BufReader::new(file))
+    };
+    let start = reader.fill_buf().with_context(|| format!("Couldn't read {}", path.display()))?;
+    match Compression::detect(start) {
+        Some(compression) => Ok(Box::new(BufReader::new(compression.decoder(reader)?))),
+        None => Ok(reader),
+    }
+}
+
+/// Reads the paths listed in a manifest file, one per line, skipping blank
+/// lines and `#` comments. Every listed file has to exist.
+pub fn read_manifest(manifest: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let text = fs::read_to_string(manifest).with_context(|| format!("Couldn't open manifest {}", manifest.display()))?;
+    let mut paths = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let path = PathBuf::from(line);
+        anyhow::ensure!(
+            path.is_file(),
+            "File {} listed in manifest {} doesn't exist",
+            path.display(),
+            manifest.display()
+        );
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::{parse_lines, Data, ParseOptions};
+
+    const RUNS: &str = "\
+PS_100/NG_50/run_1.output: 4.5
+PS_100/NG_50/run_1.output:SUCCESS
+PS_100/NG_50/run_2.output: 6.0
+PS_200/NG_50/run_1.output: 3.25
+";
+
+    fn file_with(bytes: &[u8]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(bytes).unwrap();
+        file
+    }
+
+    fn data(source: &Source) -> Data {
+        let (lines, _) = parse_lines(source.text().unwrap(), &ParseOptions::default()).unwrap();
+        lines.iter().collect()
+    }
+
+    #[test]
+    fn mapping_a_file_reads_the_same_data() {
+        let file = file_with(RUNS.as_bytes());
+        let mapped = Source::open(file.path(), true, false).unwrap();
+        let read = Source::open(file.path(), false, false).unwrap();
+        assert!(matches!(mapped, Source::Mapped(_)));
+        assert_eq!(mapped.text().unwrap(), read.text().unwrap());
+        assert_eq!(data(&mapped), data(&read));
+    }
+
+    #[test]
+    fn a_manifest_lists_existing_files() {
+        let (first, second) = (file_with(RUNS.as_bytes()), file_with(RUNS.as_bytes()));
+        let contents = format!("# runs for today\n{}\n\n  {}  \n", first.path().display(), second.path().display());
+        let manifest = file_with(contents.as_bytes());
+        assert_eq!(read_manifest(manifest.path()).unwrap(), [first.path(), second.path()]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.output");
+        let manifest = file_with(format!("{}\n{}\n", first.path().display(), missing.display()).as_bytes());
+        let error = read_manifest(manifest.path()).unwrap_err().to_string();
+        assert!(error.contains(&missing.display().to_string()), "{error}");
+    }
+
+    #[test]
+    fn lossy_drops_only_the_invalid_lines() {
+        let mut bytes = b"PS_100/NG_50/run_1.output: 4.5\nPS_100/NG_50/run_2.output: \xff\xfe\n".to_vec();
+        bytes.extend_from_slice(b"PS_200/NG_50/run_1.output: 3.25\n");
+        let file = file_with(&bytes);
+        for mmap in [false, true] {
+            assert!(Source::open(file.path(), mmap, false).and_then(|source| source.text().map(str::len)).is_err());
+            let source = Source::open(file.path(), mmap, true).unwrap();
+            assert_eq!(source.text().unwrap(), "PS_100/NG_50/run_1.output: 4.5\nPS_200/NG_50/run_1.output: 3.25\n");
+            assert_eq!(data(&source).keys().copied().collect::<Vec<_>>(), [(100, 50), (200, 50)]);
+        }
+    }
+
+    #[test]
+    fn compressed_input_reads_the_same_as_plain() {
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(RUNS.as_bytes()).unwrap();
+        let encoded = [gzip.finish().unwrap(), zstd::encode_all(RUNS.as_bytes(), 0).unwrap()];
+
+        let plain = data(&Source::open(file_with(RUNS.as_bytes()).path(), false, false).unwrap());
+        for bytes in encoded {
+            let file = file_with(&bytes);
+            for mmap in [false, true] {
+                assert_eq!(data(&Source::open(file.path(), mmap, false).unwrap()), plain);
+            }
+            let mut streamed = String::new();
+            open_reader(file.path()).unwrap().read_to_string(&mut streamed).unwrap();
+            assert_eq!(streamed, RUNS);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use clap::ValueEnum;
+use rand::{rngs::StdRng, RngExt};
+use statrs::distribution::{ContinuousCDF, StudentsT};
+
+use crate::{mean, percentile, sem};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CiMethod {
+    /// Percentile bootstrap: resample the run times and take the spread of
+    /// the resampled means
+    Bootstrap,
+    /// Student's t: mean ± t(α/2, n - 1) · SEM, better suited to small samples
+    T,
+}
+
+/// The Student's t confidence interval for the mean of `vals`. Needs at
+/// least two values.
+pub fn t_confidence_interval(vals: &[f32], confidence: f64) -> Option<(f32, f32)> {
+    if vals.len() < 2 {
+        return None;
+    }
+    let dist = StudentsT::new(0.0, 1.0, (vals.len() - 1) as f64).ok()?;
+    let t = dist.inverse_cdf(1.0 - (1.0 - confidence) / 2.0);
+    let mean = f64::from(mean(vals));
+    let half_width = t * f64::from(sem(vals));
+    Some(((mean - half_width) as f32, (mean + half_width) as f32))
+}
+
+/// A percentile bootstrap confidence interval for the mean of `vals`, from
+/// `resamples` resamples drawn with `rng`.
+pub fn bootstrap_interval(vals: &[f32], confidence: f64, resamples: usize, rng: &mut StdRng) -> Option<(f32, f32)> {
+    if vals.is_empty() || resamples == 0 {
+        return None;
+    }
+    let mut sample = vec![0.0; vals.len()];
+    let mut means = (0..resamples)
+        .map(|_| {
+            for v in &mut sample {
+                *v = vals[rng.random_range(0..vals.len())];
+            }
+            mean(&sample)
+        })
+        .collect::<Vec<_>>();
+    means.sort_unstable_by(f32::total_cmp);
+    let tail = ((1.0 - confidence) / 2.0) as f32;
+    Some((percentile(&means, tail), percentile(&means, 1.0 - tail)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn t_interval_for_one_to_five() {
+        // 3 ± t(0.025, 4) · sqrt(2.5 / 5), with t(0.025, 4) = 2.776445.
+        let (low, high) = t_confidence_interval(&[1.0, 2.0, 3.0, 4.0, 5.0], 0.95).unwrap();
+        assert!((low - 1.0368).abs() < 1e-4, "low = {low}");
+        assert!((high - 4.9632).abs() < 1e-4, "high = {high}");
+
+        assert_eq!(t_confidence_interval(&[1.0], 0.95), None);
+    }
+}
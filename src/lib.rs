@@ -0,0 +1,23 @@
+//! Parsing and summarizing the run output of `(population size, generations)`
+//! configurations. The `process_data` binary is a command line front end to
+//! this.
+
+pub mod accumulator;
+pub mod cache;
+pub mod compare;
+pub mod formula;
+pub mod input;
+pub mod intervals;
+pub mod output;
+pub mod parse;
+pub mod profile;
+pub mod runs;
+pub mod significance;
+pub mod stats;
+pub mod tui;
+
+pub use parse::{check_schema, parse_line, parse_lines, parse_numbered_line, Entry, KeyStyle, Line, OutcomeCheck, ParseError, ParseOptions, SuccessDetails, Unit};
+pub use stats::{
+    approx_eq, bin_gens, config_rng, data_seed, data_to_stats, fit_gens, group_data, histogram, linear_fit, mean, median, normalize_means, outliers, percentile, result_to_stat, risk_adjusted_time, sem, sorted_entries, success_time_auc, totals, variance,
+    Data, GroupBy, LinearFit, MedianMethod, Totals, DEFAULT_ABSOLUTE_ERROR, DEFAULT_RELATIVE_ERROR, Normalize, Result, Stat, Stats, StatsOptions,
+};
@@ -1,176 +1,1226 @@
-use std::{fs, collections::HashMap};
+use std::{slice, collections::HashSet, fs::{self, File, OpenOptions}, io::{self, BufRead, IsTerminal, Write}, path::{Path, PathBuf}};
 
 use anyhow::Context;
-use nom::{
-    bytes::complete::tag,
-    character::complete::{char, u32, space0},
-    combinator::map,
-    sequence::{preceded, separated_pair, tuple},
-    IResult, branch::alt, number::complete::float,
+use clap::{Parser, Subcommand};
+use process_data::{
+    accumulator, bin_gens, cache, fit_gens, formula, check_schema, compare, data_seed, data_to_stats, group_data, input, intervals, normalize_means, output, parse_lines,
+    parse_numbered_line, profile, runs, significance, totals, tui, MedianMethod, DEFAULT_RELATIVE_ERROR, Data, GroupBy, KeyStyle, Line, Normalize, OutcomeCheck, ParseOptions, Stats, StatsOptions,
 };
+use regex::Regex;
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
 
-// fn parse_results(input: &str) -> IResult<&str, Summary> {
-//     let (input, _) = tag("#")(input)?;
-//     let (input, (red, green, blue)) = (hex_primary, hex_primary, hex_primary).parse(input)?;
-//     Ok((input, Color { red, green, blue }))
-//   }
-
-#[derive(Debug, Default)]
-struct Result {
-    num_runs: usize,
-    num_successes: usize,
-    run_times: Vec<f32>,
-}
-
-type Data = HashMap<(u32, u32), Result>;
-
-#[derive(Debug)]
-struct Stat {
-    result: Result,
-    mean_run_time: f32,
-    median_run_time: f32,
-    successes_per_mean: f32,
-    successes_per_median: f32,
-}
-
-type Stats = HashMap<(u32, u32), Stat>;
-
-#[derive(Debug)]
-enum Entry {
-    Success,
-    RunTime(f32),
-}
-
-#[derive(Debug)]
-struct Line {
-    population_size: u32,
-    num_generations: u32,
-    run_number: u32,
-    entry: Entry,
-}
-
-impl<'a> FromIterator<&'a Line> for Data {
-    fn from_iter<T: IntoIterator<Item = &'a Line>>(iter: T) -> Self {
-        let mut data = Self::new();
-        for line in iter {
-            let key = (line.population_size, line.num_generations);
-            let result = data.entry(key).or_default();
-            match line.entry {
-                Entry::Success => {
-                    result.num_successes += 1;
-                },
-                Entry::RunTime(value) => {
-                    result.num_runs += 1;
-                    result.run_times.push(value);
-                },
+#[derive(Debug, Parser)]
+#[command(about = "Summarize run times and successes across (population size, generations) configurations")]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The run output files to process and merge, with `-` for stdin.
+    /// Defaults to ../all_runs.output when neither these nor --files-from
+    /// are given
+    inputs: Vec<PathBuf>,
+
+    /// Also process every file listed in this manifest, one path per line;
+    /// blank lines and lines starting with `#` are ignored
+    #[arg(long, value_name = "MANIFEST")]
+    files_from: Option<PathBuf>,
+
+    /// Log more detail to stderr (-v for info, -vv for debug); RUST_LOG
+    /// overrides this
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Skip lines that can't be parsed, and repeated SUCCESS entries for a
+    /// run, logging a warning for each, instead of stopping at the first one
+    #[arg(long)]
+    lenient: bool,
+
+    /// With --lenient, still exit with an error (after writing the output)
+    /// if any lines were skipped. Bypasses --cache, which doesn't record them
+    #[arg(long, requires = "lenient")]
+    fail_on_parse_warnings: bool,
+
+    /// Accept extra path segments before `/run_` (like `PS_100/NG_50/trial_2/run_3.output`),
+    /// merging them into their (population size, generations) configuration
+    #[arg(long)]
+    relaxed_paths: bool,
+
+    /// How paths write the population size and generations: `prefix` for
+    /// `PS_100/NG_50/run_3.output`, `query` for `pop=100&gen=50/run_3.output`
+    #[arg(long, value_enum, default_value_t = KeyStyle::Prefix)]
+    key_style: KeyStyle,
+
+    /// Ignore a last line with no trailing newline, e.g. when reading a log
+    /// that is still being written
+    #[arg(long)]
+    skip_incomplete_last_line: bool,
+
+    /// Require every labeled (`time=12.5 evals=3000`) line of a configuration
+    /// to have the same keys, failing on the first one that doesn't
+    #[arg(long, conflicts_with = "stream_every")]
+    strict_schema: bool,
+
+    /// Skip (and warn about) input lines that aren't valid UTF-8 instead of
+    /// failing on them; the line numbers in later messages don't count them
+    #[arg(long, conflicts_with = "stream_every")]
+    lossy: bool,
+
+    /// Parse with at most this many threads (default: one per core); 1
+    /// parses sequentially
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    threads: Option<u16>,
+
+    /// Memory-map the input instead of reading it into memory (ignored for stdin)
+    #[arg(long)]
+    mmap: bool,
+
+    /// How to format the computed stats
+    #[arg(long, value_enum, default_value_t = output::Format::Table)]
+    format: output::Format,
+
+    /// Write the stats to this file instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Also write the stats as CSV to this file, while stdout gets them in
+    /// --format
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["output", "stream_every"])]
+    tee: Option<PathBuf>,
+
+    /// Append to the output file rather than overwriting it; the header is
+    /// only written if the file is missing or empty
+    #[arg(long, requires = "output")]
+    append: bool,
+
+    /// With --format json, also include each configuration's run times; with
+    /// --format html, a histogram of them
+    #[arg(long)]
+    include_raw: bool,
+
+    /// After the stats, write the number of runs and successes, the overall
+    /// success rate, and the total run time across every configuration. Only
+    /// for --format table and grid, since a second table would break the
+    /// other formats' documents
+    #[arg(long, conflicts_with = "stream_every")]
+    totals: bool,
+
+    /// Fill grid cells that have no data by interpolating between their
+    /// neighbors; filled cells are marked with a `*`. Only for --format grid
+    #[arg(long)]
+    interpolate: bool,
+
+    /// Write numbers with SI suffixes (like 1.2k or 56.7µ) in the table,
+    /// grid, and report output; CSV, TSV, JSON, HTML, and Parquet stay exact
+    #[arg(long)]
+    compact: bool,
+
+    /// Add a derived column normalizing a stat against the best configuration
+    #[arg(long, value_enum)]
+    normalize: Option<Normalize>,
+
+    /// Add a `formula` column computed from the other columns by this
+    /// arithmetic expression, e.g. `num_successes / (mean_run_time * num_gens)`
+    #[arg(long, value_name = "EXPR", value_parser = formula::parse_formula)]
+    metric_formula: Option<formula::Formula>,
+
+    /// Time each phase (read, parse, aggregate, stats, sort, output) and
+    /// print the breakdown to stderr at the end
+    #[arg(long)]
+    profile: bool,
+
+    /// Print a legend defining each output column to stderr before the
+    /// stats, so stdout stays parseable
+    #[arg(long)]
+    explain: bool,
+
+    /// Accept run times written with comma thousands separators, like `1,234.5`
+    #[arg(long)]
+    allow_thousands_sep: bool,
+
+    /// What to print for stats that have no value
+    #[arg(long, default_value = "-")]
+    na_string: String,
+
+    /// Instead of the stats, print how the mean run time and success rate
+    /// estimates for one configuration (as POP:GENS) settle as runs are added
+    #[arg(long, value_name = "POP:GENS", value_parser = parse_config, conflicts_with = "stream_every")]
+    learning_curve: Option<(u32, u32)>,
+
+    /// Instead of the stats, test whether two configurations' run times
+    /// differ, and estimate how often a run of the first is faster
+    #[arg(long, num_args = 2, value_names = ["POP:GENS", "POP:GENS"], value_parser = parse_config, conflicts_with = "stream_every")]
+    compare_configs: Option<Vec<(u32, u32)>>,
+
+    /// With --compare-configs, also estimate the runs per configuration
+    /// needed to detect a mean run time difference of D at 80% power, using
+    /// the observed variances and --confidence
+    #[arg(long, value_name = "D", value_parser = parse_effect, requires = "compare_configs")]
+    sample_size_for_effect: Option<f64>,
+
+    /// The significance test used by --compare-configs
+    #[arg(long, value_enum, default_value_t = significance::Test::Welch)]
+    test: significance::Test,
+
+    /// Pool configurations along one axis; the other axis is reported as 0
+    #[arg(long, value_enum)]
+    group_by: Option<GroupBy>,
+
+    /// Pool generation counts into bins this wide (0 to W-1, W to 2W-1, ...),
+    /// each reported by its lower bound
+    #[arg(long, value_name = "W", value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "stream_every")]
+    gens_bin_width: Option<u32>,
+
+    /// Confidence level for the reported intervals
+    #[arg(long, default_value_t = 0.95, value_parser = parse_confidence)]
+    confidence: f64,
+
+    /// Which middle value to use as the median of an even number of run times
+    #[arg(long, value_enum, default_value_t = MedianMethod::Average)]
+    median_method: MedianMethod,
+
+    /// Weight of the standard deviation in risk_adjusted_time (mean_run_time
+    /// + lambda * std_dev_run_time); 0 makes it the mean
+    #[arg(long, value_name = "LAMBDA", default_value_t = 1.0)]
+    risk_lambda: f32,
+
+    /// Add a confidence interval around the mean run time, computed this way
+    #[arg(long, value_enum)]
+    ci_method: Option<intervals::CiMethod>,
+
+    /// Number of resamples for `--ci-method bootstrap`
+    #[arg(long, default_value_t = 1000)]
+    resamples: usize,
+
+    /// Seed for the bootstrap, for reproducible intervals
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Seed the bootstrap from a hash of the parsed data, so the same input
+    /// always gives the same intervals without picking a --seed
+    #[arg(long, conflicts_with_all = ["seed", "stream_every"])]
+    seed_from_input: bool,
+
+    /// Only keep lines whose path (the part before the `:`) matches this regex
+    #[arg(long, value_name = "REGEX")]
+    config_regex: Option<Regex>,
+
+    /// Drop these configurations, given as a comma-separated list of
+    /// POP:GENS like `100:50,500:100`
+    #[arg(long, value_name = "POP:GENS,...", value_delimiter = ',', value_parser = parse_config)]
+    exclude_configs: Vec<(u32, u32)>,
+
+    /// Only keep the first N distinct configurations encountered (after
+    /// --config-regex and --exclude-configs), for a quick look at a huge input
+    #[arg(long, value_name = "N")]
+    limit_configs: Option<usize>,
+
+    /// Instead of the stats, report each configuration's fastest successful
+    /// run, i.e. its time to first solution
+    #[arg(long, conflicts_with = "stream_every")]
+    first_success_time: bool,
+
+    /// Instead of the stats, write every parsed (and kept) line back out in
+    /// canonical form
+    #[arg(long, conflicts_with = "stream_every")]
+    dump_runs: bool,
+
+    /// Instead of the stats, report when 25%, 50%, 75%, and 90% of each
+    /// configuration's successful runs had finished
+    #[arg(long, conflicts_with = "stream_every")]
+    success_cdf: bool,
+
+    /// Check that dumping the parsed lines and parsing them again gives the
+    /// same data
+    #[arg(long, hide = true, conflicts_with = "stream_every")]
+    self_check: bool,
+
+    /// Order of the two key columns in tabular output: `pop,gens` or `gens,pop`
+    #[arg(long, default_value = "pop,gens")]
+    key_order: output::KeyOrder,
+
+    /// Sort configurations by this column (ascending) instead of by key; in
+    /// table format it replaces the default successes-per-mean/median tables
+    #[arg(long, value_name = "COLUMN", value_parser = output::parse_column)]
+    sort_by: Option<&'static output::Column>,
+
+    /// Break --sort-by ties by key, so the order is reproducible (the default)
+    #[arg(long, overrides_with = "no_sort_stable")]
+    sort_stable: bool,
+
+    /// Leave --sort-by ties in an arbitrary order, which sorts large inputs
+    /// faster
+    #[arg(long, overrides_with = "sort_stable")]
+    no_sort_stable: bool,
+
+    /// Instead of the stats, print only the configuration with the best value
+    /// of this column, as `pop_size=<pop>` and `num_gens=<gens>` lines. Ties go
+    /// to the smallest configuration
+    #[arg(long, value_name = "COLUMN", value_parser = output::parse_ranked_column, conflicts_with = "stream_every")]
+    best: Option<&'static output::Column>,
+
+    /// Instead of the stats, fit mean run time as a linear function of the
+    /// number of generations across this population size's configurations,
+    /// printing the slope, intercept, and R²
+    #[arg(long, value_name = "POP", conflicts_with = "stream_every")]
+    fit_gens: Option<u32>,
+
+    /// Instead of a row per configuration, write a multi-line report for
+    /// each one with every stat, its outliers, and a histogram of its run
+    /// times (ordered by --sort-by, if given)
+    #[arg(long, conflicts_with_all = ["stream_every", "best"])]
+    report: bool,
+
+    /// Browse the stats interactively as a grid of --grid-metric, with the
+    /// selected configuration's full stats alongside. Falls back to the usual
+    /// output when stdout isn't a terminal
+    #[arg(long, conflicts_with_all = ["stream_every", "best"])]
+    tui: bool,
+
+    /// The stat shown in each cell of `--format grid` (and `--tui`)
+    #[arg(long, value_name = "COLUMN", default_value = "successes_per_mean", value_parser = output::parse_column)]
+    grid_metric: &'static output::Column,
+
+    /// Use exactly these population sizes as the grid rows (comma-separated),
+    /// even if some have no data
+    #[arg(long, value_delimiter = ',')]
+    pop_values: Option<Vec<u32>>,
+
+    /// Use exactly these generation counts as the grid columns (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    gens_values: Option<Vec<u32>>,
+
+    /// Reuse stats computed by an earlier run on the same (unchanged) inputs
+    /// and settings, storing them in this directory
+    #[arg(long, value_name = "DIR")]
+    cache: Option<PathBuf>,
+
+    /// Ignore --cache and always recompute
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Read the inputs a line at a time, writing updated stats after every N
+    /// lines (and once more at the end) instead of only at the end
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["group_by", "mmap", "cache"])]
+    stream_every: Option<u64>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Compare one stat between datasets, configuration by configuration: the
+    /// change between two datasets, or with more than two each dataset's rank
+    /// and its average rank. Parsing and aggregation options go before `compare`
+    Compare(CompareArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct CompareArgs {
+    /// The run outputs to compare; with two, the first is the baseline
+    #[arg(num_args = 2.., required = true)]
+    datasets: Vec<PathBuf>,
+
+    /// The stat to compare (any numeric column name). Ranking needs one with
+    /// a better direction, like mean_run_time or success_rate
+    #[arg(long, default_value = "mean_run_time", value_parser = output::parse_column)]
+    metric: &'static output::Column,
+
+    /// Treat values no further apart than this as the same: unchanged with
+    /// two datasets, tied when ranking
+    #[arg(long, default_value_t = 1e-3)]
+    epsilon: f32,
+
+    /// Treat --epsilon as a fraction of the larger value instead of an
+    /// absolute difference
+    #[arg(long, conflicts_with = "relative_error")]
+    relative: bool,
+
+    /// Also treat values as the same when they're within this fraction of
+    /// the larger one
+    #[arg(long, default_value_t = DEFAULT_RELATIVE_ERROR)]
+    relative_error: f32,
+
+    /// Only show configurations that changed or are in just one dataset
+    /// (two datasets only)
+    #[arg(long)]
+    diff_only: bool,
+}
+
+impl CompareArgs {
+    fn tolerance(&self) -> compare::Tolerance {
+        if self.relative {
+            compare::Tolerance {
+                relative: self.epsilon,
+                absolute: 0.0,
+            }
+        } else {
+            compare::Tolerance {
+                relative: self.relative_error,
+                absolute: self.epsilon,
             }
         }
-        data
     }
 }
 
-fn median(vals: &mut[f32]) -> f32 {
-    vals.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
-    if vals.len() % 2 == 1 {
-        vals[vals.len() / 2]
-    } else {
-        (vals[vals.len() / 2 - 1] + vals[vals.len() / 2]) / 2.0
+impl Args {
+    /// The explicit inputs followed by any from the manifest.
+    fn input_paths(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let mut paths = self.inputs.clone();
+        if let Some(manifest) = &self.files_from {
+            paths.extend(input::read_manifest(manifest)?);
+        }
+        if paths.is_empty() {
+            paths.push(PathBuf::from("../all_runs.output"));
+        }
+        Ok(paths)
+    }
+
+    /// Whether one of the modes that replace the normal stats output is on.
+    fn raw_mode(&self) -> bool {
+        self.dump_runs
+            || self.self_check
+            || self.learning_curve.is_some()
+            || self.first_success_time
+            || self.success_cdf
+            || self.compare_configs.is_some()
+    }
+
+    /// Whether `line` passes --config-regex and --exclude-configs.
+    fn keeps(&self, line: &Line) -> bool {
+        self.config_regex.as_ref().is_none_or(|regex| regex.is_match(line.path))
+            && !self.exclude_configs.contains(&(line.population_size, line.num_generations))
+    }
+
+    /// Everything besides the inputs themselves that affects the computed
+    /// stats, for keying the cache.
+    fn stats_fingerprint(&self) -> String {
+        format!(
+            "{:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+            self.parse_options(),
+            self.strict_schema,
+            self.lossy,
+            self.config_regex.as_ref().map(Regex::as_str),
+            self.exclude_configs,
+            self.metric_formula.as_ref().map(formula::Formula::source),
+            self.limit_configs,
+            self.group_by,
+            self.gens_bin_width,
+            self.stats_options(),
+            self.seed_from_input,
+            self.normalize,
+        )
+    }
+
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            allow_thousands_sep: self.allow_thousands_sep,
+            lenient: self.lenient,
+            relaxed_paths: self.relaxed_paths,
+            key_style: self.key_style,
+            skip_incomplete_last_line: self.skip_incomplete_last_line,
+        }
+    }
+
+    fn stats_options(&self) -> StatsOptions {
+        StatsOptions {
+            confidence: self.confidence,
+            ci_method: self.ci_method,
+            resamples: self.resamples,
+            seed: self.seed,
+            median_method: self.median_method,
+            risk_lambda: self.risk_lambda,
+        }
+    }
+
+    fn output_options(&self, include_header: bool) -> output::OutputOptions {
+        output::OutputOptions {
+            format: self.format,
+            include_header,
+            na_string: self.na_string.clone(),
+            key_order: self.key_order,
+            sort_by: self.sort_by,
+            sort_stable: !self.no_sort_stable,
+            grid_metric: self.grid_metric,
+            pop_values: self.pop_values.clone(),
+            gens_values: self.gens_values.clone(),
+            include_raw: self.include_raw,
+            interpolate: self.interpolate,
+            compact: self.compact,
+        }
     }
 }
 
-fn mean(vals: &[f32]) -> f32 {
-    vals.iter().sum::<f32>() / vals.len() as f32
+fn parse_confidence(s: &str) -> std::result::Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(c) if c > 0.0 && c < 1.0 => Ok(c),
+        Ok(c) => Err(format!("confidence must be strictly between 0 and 1, got {c}")),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
-fn data_to_stats(data: Data) -> Stats {
-    let mut stats = Stats::new();
-    for (key, result) in data {
-        let successes = result.num_successes as f32;
-        let mut run_times = result.run_times.clone();
-        let mean_run_time = mean(&run_times);
-        let median_run_time = median(&mut run_times);
-        stats.insert(key, Stat { result, mean_run_time, median_run_time, successes_per_mean: successes / mean_run_time, successes_per_median: successes / median_run_time });
+fn parse_effect(s: &str) -> std::result::Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(d) if d > 0.0 && d.is_finite() => Ok(d),
+        Ok(d) => Err(format!("effect size must be positive, got {d}")),
+        Err(e) => Err(e.to_string()),
     }
-    stats
 }
 
-fn pop_size(s: &str) -> IResult<&str, u32> {
-    preceded(tag("PS_"), u32)(s)
+/// Parses a configuration written as `POP:GENS`, e.g. `100:50`.
+fn parse_config(s: &str) -> std::result::Result<(u32, u32), String> {
+    let (pop_size, num_gens) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected POP:GENS, got `{s}`"))?;
+    let parse = |part: &str| part.trim().parse::<u32>().map_err(|e| format!("bad number `{part}` in `{s}`: {e}"));
+    Ok((parse(pop_size)?, parse(num_gens)?))
 }
 
-fn num_gens(s: &str) -> IResult<&str, u32> {
-    preceded(tag("/NG_"), u32)(s)
+fn has_content(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.len() > 0)
 }
 
-fn run_num(s: &str) -> IResult<&str, u32> {
-    // run_29.output
-    map(
-        tuple((tag("/run_"), u32, tag(".output"))),
-        |(_, run_num, _)| run_num,
-    )(s)
+/// Opens wherever output should go, and reports whether a header should be
+/// written (it shouldn't when appending to a file that already has content).
+fn open_output(args: &Args) -> anyhow::Result<(Box<dyn Write>, bool)> {
+    match &args.output {
+        Some(output_path) => {
+            let include_header = !(args.append && has_content(output_path));
+            let file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(args.append)
+                .truncate(!args.append)
+                .open(output_path)
+                .with_context(|| format!("Couldn't open output file {}", output_path.display()))?;
+            Ok((Box::new(file), include_header))
+        }
+        None => Ok((Box::new(io::stdout().lock()), true)),
+    }
 }
 
-fn path(s: &str) -> IResult<&str, (u32, u32, u32)> {
-    tuple((pop_size, num_gens, run_num))(s)
+fn dump_runs(out: &mut impl Write, lines: &[Line]) -> io::Result<()> {
+    for line in lines {
+        writeln!(out, "{line}")?;
+    }
+    Ok(())
+}
+
+/// Round-trips `lines` through `--dump-runs` and the parser, failing if the
+/// aggregated data changes along the way.
+fn self_check(lines: &[Line], parse_options: &ParseOptions) -> anyhow::Result<()> {
+    let mut dumped = Vec::new();
+    dump_runs(&mut dumped, lines)?;
+    let dumped = String::from_utf8(dumped)?;
+    // The dump is always in the canonical `PS_`/`NG_` form.
+    let strict = ParseOptions {
+        lenient: false,
+        key_style: KeyStyle::Prefix,
+        ..*parse_options
+    };
+    let (reparsed, _) = parse_lines(&dumped, &strict).context("Self-check couldn't re-parse the dumped runs")?;
+
+    let original: Data = lines.iter().collect();
+    let round_tripped: Data = reparsed.iter().collect();
+    anyhow::ensure!(
+        original == round_tripped,
+        "Self-check failed: re-parsing the dumped runs gave different data"
+    );
+    println!("Self-check passed: {} lines, {} configurations", lines.len(), original.len());
+    Ok(())
+}
+
+/// Sends logs to stderr, keeping stdout for the stats themselves.
+fn init_logging(verbose: u8) {
+    let default_level = match verbose {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .init();
 }
 
-fn success(s: &str) -> IResult<&str, Entry> {
-    map(tag("SUCCESS"), |_| Entry::Success)(s)
+/// How many input lines were parsed (whether or not a filter then dropped
+/// them), how many `--lenient` skipped as unparseable, and how many repeated
+/// `SUCCESS` lines it ignored.
+#[derive(Debug, Default, Clone, Copy)]
+struct LineCounts {
+    parsed: usize,
+    skipped: usize,
+    repeated_successes: usize,
+}
+
+/// Parses `text` and applies the line filters from `args`, also returning
+/// how many lines were parsed and skipped.
+fn read_lines<'a>(text: &'a str, args: &Args) -> anyhow::Result<(Vec<Line<'a>>, LineCounts)> {
+    let (mut lines, skipped) = parse_lines(text, &args.parse_options())?;
+    let parsed = lines.len();
+    lines.retain(|line| args.keeps(line));
+    Ok((lines, LineCounts { parsed, skipped, ..LineCounts::default() }))
+}
+
+/// Tracks the configurations seen so far for `--limit-configs`, letting
+/// through the lines of the first `limit` distinct ones in the order they're
+/// encountered.
+struct ConfigLimit {
+    limit: Option<usize>,
+    seen: HashSet<(u32, u32)>,
+}
+
+impl ConfigLimit {
+    fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            seen: HashSet::new(),
+        }
+    }
+
+    fn admits(&mut self, line: &Line) -> bool {
+        let Some(limit) = self.limit else {
+            return true;
+        };
+        let key = (line.population_size, line.num_generations);
+        self.seen.contains(&key) || (self.seen.len() < limit && self.seen.insert(key))
+    }
 }
 
-fn run_time(s: &str) -> IResult<&str, Entry> {
-    map(preceded(space0, float), |run_time| Entry::RunTime(run_time))(s)
+/// Aggregates `lines`, binning and regrouping them if `args` asks for it.
+fn lines_to_data(lines: &[Line], args: &Args) -> Data {
+    let mut data: Data = lines.iter().collect();
+    if let Some(width) = args.gens_bin_width {
+        data = bin_gens(data, width);
+    }
+    match args.group_by {
+        Some(group_by) => group_data(data, group_by),
+        None => data,
+    }
 }
 
-fn entry(s: &str) -> IResult<&str, Entry> {
-    alt((success, run_time))(s)
+fn compute_stats(data: Data, args: &Args) -> Stats {
+    let mut options = args.stats_options();
+    if args.seed_from_input {
+        options.seed = Some(data_seed(&data));
+    }
+    let mut stats = data_to_stats(data, &options);
+    info!(configurations = stats.len(), "computed stats");
+    match args.normalize {
+        Some(Normalize::Mean) => normalize_means(&mut stats),
+        None => {}
+    }
+    if let Some(formula) = &args.metric_formula {
+        formula::apply_formula(&mut stats, formula);
+    }
+    stats
 }
 
-fn line(s: &str) -> IResult<&str, Line> {
-    map(
-        separated_pair(path, char(':'), entry),
-        |((population_size, num_generations, run_number), entry)| Line {
-            population_size,
-            num_generations,
-            run_number,
-            entry,
-        },
-    )(s)
+/// Opens and parses every one of `sources`, also returning how many lines
+/// were parsed, skipped, and ignored as repeated successes.
+fn read_all_lines<'a>(sources: &'a [input::Source], args: &Args) -> anyhow::Result<(Vec<Line<'a>>, LineCounts)> {
+    let mut lines = Vec::new();
+    let mut counts = LineCounts::default();
+    for source in sources {
+        let (source_lines, source_counts) = read_lines(source.text()?, args)?;
+        lines.extend(source_lines);
+        counts.parsed += source_counts.parsed;
+        counts.skipped += source_counts.skipped;
+    }
+    let mut outcomes = OutcomeCheck::default();
+    let mut counted = Vec::with_capacity(lines.len());
+    for line in lines {
+        if outcomes.admits(&line, args.lenient)? {
+            counted.push(line);
+        } else {
+            counts.repeated_successes += 1;
+        }
+    }
+    let mut lines = counted;
+    let mut limit = ConfigLimit::new(args.limit_configs);
+    lines.retain(|line| limit.admits(line));
+    if args.strict_schema {
+        check_schema(&lines)?;
+    }
+    Ok((lines, counts))
 }
 
-fn parse_line(s: &str) -> anyhow::Result<Line> {
-    let (_, l) = line(s).map_err(nom::Err::<nom::error::Error<&str>>::to_owned)?;
-    Ok(l)
+fn open_sources(paths: &[PathBuf], args: &Args) -> anyhow::Result<Vec<input::Source>> {
+    paths.iter().map(|path| input::Source::open(path, args.mmap, args.lossy)).collect()
+}
+
+/// Fails if `--fail-on-parse-warnings` is set and `--lenient` skipped or
+/// ignored any lines, saying how many of each.
+fn check_parse_warnings(args: &Args, counts: LineCounts) -> anyhow::Result<()> {
+    if !args.fail_on_parse_warnings {
+        return Ok(());
+    }
+    let mut warnings = Vec::new();
+    if counts.skipped > 0 {
+        warnings.push(format!("{} lines couldn't be parsed", counts.skipped));
+    }
+    if counts.repeated_successes > 0 {
+        warnings.push(format!("{} repeated SUCCESS lines were ignored", counts.repeated_successes));
+    }
+    anyhow::ensure!(warnings.is_empty(), "{} (--fail-on-parse-warnings)", warnings.join(" and "));
+    Ok(())
+}
+
+/// Reads, parses, and summarizes the run output in `paths`, going through
+/// the `--cache` directory when there is one. Also returns how many lines
+/// were parsed, skipped, and ignored.
+fn load_stats(paths: &[PathBuf], args: &Args) -> anyhow::Result<(Stats, LineCounts)> {
+    let cache_dir = args.cache.as_deref().filter(|_| !args.no_cache && !args.fail_on_parse_warnings);
+    let cache_key = match cache_dir {
+        Some(_) => cache::key(paths, &args.stats_fingerprint())?,
+        None => None,
+    };
+    if let (Some(dir), Some(key)) = (cache_dir, &cache_key) {
+        if let Some((stats, parsed)) = cache::load(dir, key) {
+            return Ok((stats, LineCounts { parsed, ..LineCounts::default() }));
+        }
+    }
+
+    let sources = profile::time("read", || open_sources(paths, args))?;
+    let (lines, counts) = profile::time("parse", || read_all_lines(&sources, args))?;
+    let data = profile::time("aggregate", || lines_to_data(&lines, args));
+    let stats = profile::time("stats", || compute_stats(data, args));
+
+    if let (Some(dir), Some(key)) = (cache_dir, &cache_key) {
+        cache::store(dir, key, &stats, counts.parsed)?;
+    }
+    Ok((stats, counts))
+}
+
+fn run_compare(args: &Args, compare_args: &CompareArgs) -> anyhow::Result<()> {
+    let mut datasets = Vec::with_capacity(compare_args.datasets.len());
+    let mut counts = LineCounts::default();
+    for path in &compare_args.datasets {
+        let (stats, dataset_counts) = load_stats(slice::from_ref(path), args)?;
+        datasets.push(stats);
+        counts.skipped += dataset_counts.skipped;
+        counts.repeated_successes += dataset_counts.repeated_successes;
+    }
+    match datasets.as_slice() {
+        [first, second] => write_comparison(args, compare_args, first, second)?,
+        _ => write_ranking(args, compare_args, &datasets)?,
+    }
+    check_parse_warnings(args, counts)
+}
+
+fn write_comparison(args: &Args, compare_args: &CompareArgs, first: &Stats, second: &Stats) -> anyhow::Result<()> {
+    let rows = compare::compare(first, second, compare_args.metric, compare_args.tolerance())
+        .into_iter()
+        .filter(|(_, c)| !compare_args.diff_only || c.status.is_difference())
+        .map(|(key, c)| {
+            let values = vec![
+                c.first.into(),
+                c.second.into(),
+                c.delta.into(),
+                output::Value::Text(c.status.label()),
+            ];
+            (key, values)
+        })
+        .collect::<Vec<_>>();
+    let (mut out, include_header) = open_output(args)?;
+    output::write_keyed(
+        &mut out,
+        &["first", "second", "delta", "status"],
+        &rows,
+        &args.output_options(include_header),
+    )?;
+    Ok(())
+}
+
+/// Writes each dataset's rank for every configuration they all share,
+/// followed by each dataset's average rank.
+fn write_ranking(args: &Args, compare_args: &CompareArgs, datasets: &[Stats]) -> anyhow::Result<()> {
+    let metric = compare_args.metric;
+    let ranks = compare::rank(datasets, metric, compare_args.tolerance())
+        .with_context(|| format!("Can't rank datasets by {}, which has no better direction", metric.name))?;
+    let averages = compare::average_ranks(&ranks, datasets.len());
+
+    let names = compare_args.datasets.iter().map(|path| path.display().to_string()).collect::<Vec<_>>();
+    let header = names.iter().map(String::as_str).collect::<Vec<_>>();
+    let rows = ranks
+        .into_iter()
+        .map(|(key, ranks)| (key, ranks.into_iter().map(output::Value::Float).collect()))
+        .collect::<Vec<_>>();
+    let (mut out, include_header) = open_output(args)?;
+    let options = args.output_options(include_header);
+    output::write_keyed(&mut out, &header, &rows, &options)?;
+    writeln!(out)?;
+    let average_row = averages.into_iter().map(output::Value::Float).collect();
+    output::write_rows(&mut out, &header, &[average_row], &options)?;
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
-    let path = "../all_runs.output";
+    let args = Args::parse();
+    init_logging(args.verbose);
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.into())
+            .build_global()
+            .context("Couldn't set up the thread pool")?;
+    }
+
+    if args.profile {
+        profile::enable();
+    }
+
+    let result = run(&args);
+    if args.profile {
+        profile::write_report(&mut io::stderr().lock())?;
+    }
+    result
+}
+
+fn run(args: &Args) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !args.interpolate || args.format == output::Format::Grid,
+        "--interpolate only works with --format grid"
+    );
+    if let Some(Command::Compare(compare_args)) = &args.command {
+        return run_compare(args, compare_args);
+    }
+
+    let paths = args.input_paths()?;
+    if let Some(every) = args.stream_every {
+        return run_stream(args, &paths, every);
+    }
+    if args.raw_mode() {
+        return run_raw_mode(args, &paths);
+    }
+
+    let (stats, counts) = load_stats(&paths, args)?;
+    profile::time("output", || write_results(args, &stats, counts.parsed))?;
+    check_parse_warnings(args, counts)
+}
+
+/// Writes `stats` the way `args` asks for: just the best configuration, the
+/// TUI, a report, or the stats themselves. `lines_parsed` is reported when
+/// there are no configurations.
+fn write_results(args: &Args, stats: &Stats, lines_parsed: usize) -> anyhow::Result<()> {
+    if let Some(column) = args.best {
+        let key = output::best_config(stats, column)
+            .with_context(|| format!("No configuration has a value for {}", column.name))?;
+        let (mut out, _) = open_output(args)?;
+        output::write_assignments(&mut out, key, args.key_order)?;
+        return Ok(());
+    }
+
+    if let Some(pop_size) = args.fit_gens {
+        let fit = fit_gens(stats, pop_size)
+            .with_context(|| format!("Population size {pop_size} needs runs at two or more generation counts to fit"))?;
+        let (mut out, _) = open_output(args)?;
+        output::write_fit(&mut out, pop_size, &fit)?;
+        return Ok(());
+    }
+
+    if args.tui {
+        if io::stdout().is_terminal() {
+            let mut app = tui::App::new(stats, args.grid_metric, &args.na_string);
+            tui::run(&mut app)?;
+            return Ok(());
+        }
+        warn!("stdout isn't a terminal, so writing the stats instead of starting the TUI");
+    }
+
+    if args.explain {
+        output::write_legend(&mut io::stderr().lock(), &args.output_options(true), args.report)?;
+    }
+
+    if args.report {
+        let (mut out, _) = open_output(args)?;
+        output::write_report(&mut out, stats, lines_parsed, &args.output_options(true))?;
+        return Ok(());
+    }
+
+    anyhow::ensure!(
+        !(args.append && args.format == output::Format::Parquet),
+        "--append can't be used with --format parquet"
+    );
+    anyhow::ensure!(
+        !args.totals || matches!(args.format, output::Format::Table | output::Format::Grid),
+        "--totals only works with --format table or grid"
+    );
+    let (mut out, include_header) = open_output(args)?;
+    write_stats_and_tee(&mut out, include_header, args, stats, lines_parsed)
+}
+
+/// Writes `stats` (and `--totals`) to `out` in `--format`, and to the
+/// `--tee` file as CSV.
+fn write_stats_and_tee(out: &mut impl Write, include_header: bool, args: &Args, stats: &Stats, lines_parsed: usize) -> anyhow::Result<()> {
+    let options = args.output_options(include_header);
+    output::write_stats(out, stats, lines_parsed, &options)?;
+    if args.totals {
+        output::write_totals(out, &totals(stats), &options)?;
+    }
+    if let Some(tee_path) = &args.tee {
+        let mut file = File::create(tee_path)
+            .with_context(|| format!("Couldn't create tee file {}", tee_path.display()))?;
+        let tee_options = output::OutputOptions { format: output::Format::Csv, ..args.output_options(true) };
+        output::write_stats(&mut file, stats, lines_parsed, &tee_options)?;
+    }
+
+    Ok(())
+}
+
+/// Feeds the inputs through an `Accumulator` one line at a time, writing a
+/// snapshot of the stats every `every` lines.
+fn run_stream(args: &Args, paths: &[PathBuf], every: u64) -> anyhow::Result<()> {
+    let parse_options = args.parse_options();
+    let mut accumulator = accumulator::Accumulator::new(&args.stats_options());
+    let (mut out, mut include_header) = open_output(args)?;
+    let mut write_snapshot = |accumulator: &accumulator::Accumulator, counts: LineCounts, include_header: bool| -> anyhow::Result<()> {
+        let mut stats = accumulator.snapshot();
+        match args.normalize {
+            Some(Normalize::Mean) => normalize_means(&mut stats),
+            None => {}
+        }
+        if let Some(formula) = &args.metric_formula {
+            formula::apply_formula(&mut stats, formula);
+        }
+        output::write_stats(&mut out, &stats, counts.parsed, &args.output_options(include_header))?;
+        out.flush()?;
+        Ok(())
+    };
+
+    let mut limit = ConfigLimit::new(args.limit_configs);
+    let mut outcomes = OutcomeCheck::default();
+    let mut count = 0;
+    let mut counts = LineCounts::default();
+    let mut text = String::new();
+    for path in paths {
+        let mut reader = input::open_reader(path)?;
+        for line_number in 1.. {
+            text.clear();
+            if reader.read_line(&mut text).with_context(|| format!("Couldn't read {}", path.display()))? == 0 {
+                break;
+            }
+            // Only the last line can be missing its newline.
+            if parse_options.skip_incomplete_last_line && !text.ends_with('\n') {
+                warn!("dropping incomplete last line {text:?}");
+                break;
+            }
+            let line = parse_numbered_line(text.trim_end_matches(['\n', '\r']), line_number, &parse_options)?;
+            counts.parsed += usize::from(line.is_some());
+            counts.skipped += usize::from(line.is_none());
+            if let Some(line) = line.filter(|line| args.keeps(line)).filter(|line| limit.admits(line)) {
+                if outcomes.admits(&line, args.lenient)? {
+                    accumulator.push(&line);
+                } else {
+                    counts.repeated_successes += 1;
+                }
+            }
+            count += 1;
+            if count % every == 0 {
+                write_snapshot(&accumulator, counts, include_header)?;
+                include_header = false;
+            }
+        }
+    }
+    if count == 0 || count % every != 0 {
+        write_snapshot(&accumulator, counts, include_header)?;
+    }
+    check_parse_warnings(args, counts)
+}
+
+/// Handles the modes that work from the individual parsed lines rather than
+/// the summarized stats.
+fn run_raw_mode(args: &Args, paths: &[PathBuf]) -> anyhow::Result<()> {
+    let sources = profile::time("read", || open_sources(paths, args))?;
+    let (lines, counts) = profile::time("parse", || read_all_lines(&sources, args))?;
+    profile::time("output", || write_raw_mode(args, &lines))?;
+    check_parse_warnings(args, counts)
+}
+
+fn write_raw_mode(args: &Args, lines: &[Line]) -> anyhow::Result<()> {
+    let parse_options = args.parse_options();
 
-    let lines = fs::read_to_string(path)
-        .with_context(|| format!("Couldn't open file {path}"))?
-        .lines()
-        .map(parse_line)
-        .collect::<anyhow::Result<Vec<_>>>()?;
+    if args.dump_runs {
+        let (mut out, _) = open_output(args)?;
+        dump_runs(&mut out, lines)?;
+        return Ok(());
+    }
 
-    let data: Data = lines.iter().collect();
-    let stats = data_to_stats(data);
+    if args.self_check {
+        return self_check(lines, &parse_options);
+    }
 
-    println!("{stats:?}");
+    if let Some((pop_size, num_gens)) = args.learning_curve {
+        let runs = runs::runs_by_config(lines);
+        let config_runs = runs
+            .get(&(pop_size, num_gens))
+            .with_context(|| format!("No runs found for configuration {pop_size}:{num_gens}"))?;
+        let (mut out, include_header) = open_output(args)?;
+        output::write_learning_curve(&mut out, &runs::learning_curve(config_runs), include_header)?;
+        return Ok(());
+    }
 
-    println!();
-    println!("PopSize   NumGens SuccessesPerMean");
-    let mut pairs = stats.iter().collect::<Vec<(_, _)>>();
-    pairs.sort_unstable_by(|(_, b), (_, y)| b.successes_per_mean.partial_cmp(&y.successes_per_mean).unwrap());
-    for ((pop_size, num_gens), s) in &pairs {
-        println!("{pop_size}    {num_gens}  {}", s.successes_per_mean);
+    if args.first_success_time {
+        let runs = runs::runs_by_config(lines);
+        let rows = runs
+            .iter()
+            .map(|(key, config_runs)| {
+                let values = vec![
+                    output::Value::Integer(config_runs.values().filter(|r| r.success).count() as u64),
+                    runs::first_success_time(config_runs).into(),
+                ];
+                (*key, values)
+            })
+            .collect::<Vec<_>>();
+        let (mut out, include_header) = open_output(args)?;
+        output::write_keyed(
+            &mut out,
+            &["num_successful_runs", "first_success_time"],
+            &rows,
+            &args.output_options(include_header),
+        )?;
+        return Ok(());
     }
 
-    println!();
-    println!("PopSize   NumGens SuccessesPerMedian");
-    pairs.sort_unstable_by(|(_, b), (_, y)| b.successes_per_median.partial_cmp(&y.successes_per_median).unwrap());
-    for ((pop_size, num_gens), s) in &pairs {
-        println!("{pop_size}    {num_gens}  {}", s.successes_per_median);
+    if args.success_cdf {
+        let runs = runs::runs_by_config(lines);
+        let rows = runs
+            .iter()
+            .flat_map(|(key, config_runs)| {
+                runs::success_cdf(config_runs, &runs::CDF_FRACTIONS)
+                    .into_iter()
+                    .map(|(fraction, time)| (*key, vec![output::Value::Float(fraction), time.into()]))
+            })
+            .collect::<Vec<_>>();
+        let (mut out, include_header) = open_output(args)?;
+        output::write_keyed(
+            &mut out,
+            &["fraction", "time"],
+            &rows,
+            &args.output_options(include_header),
+        )?;
+        return Ok(());
+    }
+
+    let data = lines_to_data(lines, args);
+
+    if let Some(configs) = &args.compare_configs {
+        let run_times = |&(pop_size, num_gens): &(u32, u32)| {
+            data.get(&(pop_size, num_gens))
+                .map(|r| r.run_times.as_slice())
+                .with_context(|| format!("No runs found for configuration {pop_size}:{num_gens}"))
+        };
+        let (a, b) = (run_times(&configs[0])?, run_times(&configs[1])?);
+        let (mut out, _) = open_output(args)?;
+        match args.test {
+            significance::Test::Welch => {
+                let (t, df, p) = significance::welch_t_test(a, b);
+                output::write_welch(&mut out, configs[0], configs[1], t, df, p)?;
+            }
+        }
+        output::write_prob_faster(&mut out, configs[0], configs[1], significance::prob_a_faster(a, b))?;
+        if let Some(effect) = args.sample_size_for_effect {
+            let power = significance::DEFAULT_POWER;
+            let runs = significance::required_sample_size(a, b, effect, args.confidence, power);
+            output::write_sample_size(&mut out, effect, power, runs)?;
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUNS: &str = "PS_100/NG_50/run_1.output: 10.5
+PS_100/NG_50/run_1.output:SUCCESS
+PS_500/NG_50/run_1.output: 4
+";
+
+    fn args(argv: &[&str]) -> Args {
+        Args::try_parse_from(["process_data"].iter().chain(argv)).expect("valid arguments")
+    }
+
+    fn stats(text: &str, args: &Args) -> Stats {
+        let (lines, _) = read_lines(text, args).expect("valid input");
+        compute_stats(lines_to_data(&lines, args), args)
+    }
+
+    #[test]
+    fn appending_keeps_a_single_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.csv");
+        let args = args(&["--format", "csv", "--output", path.to_str().unwrap(), "--append"]);
+        let stats = stats(RUNS, &args);
+        for _ in 0..2 {
+            let (mut out, include_header) = open_output(&args).unwrap();
+            output::write_stats(&mut out, &stats, 3, &args.output_options(include_header)).unwrap();
+        }
+
+        let written = fs::read_to_string(&path).unwrap();
+        let lines = written.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + 2 * stats.len());
+        assert_eq!(lines.iter().filter(|line| line.starts_with("pop_size,")).count(), 1);
+        assert!(lines[0].starts_with("pop_size,num_gens,"));
+    }
+
+    #[test]
+    fn a_second_load_uses_the_cache_instead_of_parsing() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("runs.output");
+        fs::write(&input, "PS_100/NG_50/run_1.output: 4\n").unwrap();
+        let cache_dir = dir.path().join("cache");
+        let args = args(&["--cache", cache_dir.to_str().unwrap(), input.to_str().unwrap()]);
+        let paths = args.input_paths().unwrap();
+        let (first, counts) = load_stats(&paths, &args).unwrap();
+        assert_eq!(first[&(100, 50)].mean_run_time, 4.0);
+        assert_eq!(counts.parsed, 1);
+
+        // Same size and modification time, so the cache key doesn't change and
+        // only a re-parse would see the new run time.
+        let modified = fs::metadata(&input).unwrap().modified().unwrap();
+        fs::write(&input, "PS_100/NG_50/run_1.output: 8\n").unwrap();
+        File::options().write(true).open(&input).unwrap().set_modified(modified).unwrap();
+        let (second, counts) = load_stats(&paths, &args).unwrap();
+        assert_eq!(second[&(100, 50)].mean_run_time, 4.0);
+        assert_eq!(counts.parsed, 1);
+
+        let uncached = Args { no_cache: true, ..args };
+        assert_eq!(load_stats(&paths, &uncached).unwrap().0[&(100, 50)].mean_run_time, 8.0);
+    }
+
+    #[test]
+    fn config_regex_keeps_only_matching_paths() {
+        let args = args(&["--config-regex", "^PS_100/"]);
+        assert_eq!(stats(RUNS, &args).keys().copied().collect::<Vec<_>>(), [(100, 50)]);
+
+        let (lines, counts) = read_lines(RUNS, &args).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(counts.parsed, 3);
+        assert!(Args::try_parse_from(["process_data", "--config-regex", "PS_("]).is_err());
+    }
+
+    #[test]
+    fn a_small_input_survives_the_self_check() {
+        let text = "\
+PS_100/NG_50/run_1.output: 250ms
+PS_100/NG_50/run_1.output:SUCCESS 27 0.0
+PS_100/NG_50/run_2.output: time=1.5 evals=3000
+pop=200&gen=10/run_1.output: 3
+";
+        for argv in [&[][..], &["--key-style", "query"]] {
+            let args = args(argv);
+            let (lines, _) = parse_lines(text, &ParseOptions { lenient: true, ..args.parse_options() }).unwrap();
+            assert!(!lines.is_empty());
+            self_check(&lines, &args.parse_options()).unwrap();
+        }
+    }
+
+    #[test]
+    fn limit_configs_keeps_the_first_n_configurations_seen() {
+        let text = "\
+PS_500/NG_50/run_1.output: 1
+PS_100/NG_50/run_1.output: 2
+PS_300/NG_50/run_1.output: 3
+PS_500/NG_50/run_2.output: 4
+";
+        let sources = [input::Source::Text(text.to_string())];
+        let args = args(&["--limit-configs", "2"]);
+        let (lines, _) = read_all_lines(&sources, &args).unwrap();
+        let data = lines_to_data(&lines, &args);
+        assert_eq!(data.keys().copied().collect::<Vec<_>>(), [(100, 50), (500, 50)]);
+        assert_eq!(data[&(500, 50)].num_runs, 2);
+
+        let mut limit = ConfigLimit::new(Some(0));
+        assert!(!limit.admits(&lines[0]));
+    }
+
+    #[test]
+    fn tee_writes_csv_while_the_table_goes_to_stdout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.csv");
+        let args = args(&["--tee", path.to_str().unwrap()]);
+        let stats = stats(RUNS, &args);
+        let mut stdout = Vec::new();
+        write_stats_and_tee(&mut stdout, true, &args, &stats, 3).unwrap();
+
+        let table = String::from_utf8(stdout).unwrap();
+        assert!(table.contains("SuccessesPerMean"), "{table}");
+        assert!(!table.lines().any(|line| line.starts_with("pop_size,")));
+
+        let csv = fs::read_to_string(&path).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + stats.len());
+        assert!(lines[0].starts_with("pop_size,num_gens,num_runs,"));
+        assert!(lines[1].starts_with("100,50,1,1,"));
+    }
+
+    #[test]
+    fn exclude_configs_drops_only_the_named_configurations() {
+        let text = format!("{RUNS}PS_100/NG_60/run_1.output: 2\n");
+        let args = args(&["--exclude-configs", "100:50, 700:1"]);
+        assert_eq!(args.exclude_configs, [(100, 50), (700, 1)]);
+        assert_eq!(stats(&text, &args).keys().copied().collect::<Vec<_>>(), [(100, 60), (500, 50)]);
+
+        assert_eq!(parse_config("100:50"), Ok((100, 50)));
+        for malformed in ["x", "100", "100:", "100:abc", "-1:50"] {
+            assert!(parse_config(malformed).is_err(), "{malformed}");
+        }
+        assert!(Args::try_parse_from(["process_data", "--exclude-configs", "100:50,oops"]).is_err());
+    }
+
+    #[test]
+    fn raw_modes_conflict_with_stream_every() {
+        for mode in [
+            &["--dump-runs"][..],
+            &["--self-check"],
+            &["--learning-curve", "100:50"],
+            &["--first-success-time"],
+            &["--success-cdf"],
+            &["--compare-configs", "100:50", "200:50"],
+        ] {
+            let argv = [&["process_data", "--stream-every", "10"][..], mode].concat();
+            let error = Args::try_parse_from(argv).unwrap_err();
+            assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn fail_on_parse_warnings_fails_after_writing_the_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("runs.output");
+        fs::write(&input, format!("{RUNS}not a run\n")).unwrap();
+        let output = dir.path().join("stats.csv");
+        let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+        let argv = ["--lenient", "--format", "csv", "--output", output, input];
+
+        run(&args(&argv)).unwrap();
+        let error = run(&args(&[&["--fail-on-parse-warnings"][..], &argv].concat())).unwrap_err();
+        assert!(error.to_string().contains("1 lines couldn't be parsed"), "{error}");
+        assert_eq!(fs::read_to_string(output).unwrap().lines().count(), 3);
+
+        let compared = dir.path().join("compare.txt");
+        let compared = compared.to_str().unwrap();
+        let error = run(&args(&["--lenient", "--fail-on-parse-warnings", "--output", compared, "compare", input, input])).unwrap_err();
+        assert!(error.to_string().contains("2 lines couldn't be parsed"), "{error}");
+        assert_eq!(fs::read_to_string(compared).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn fail_on_parse_warnings_counts_repeated_successes_separately() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("runs.output");
+        fs::write(&input, format!("{RUNS}PS_100/NG_50/run_1.output:SUCCESS\n")).unwrap();
+        let output = dir.path().join("stats.csv");
+        let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+
+        let error = run(&args(&["--lenient", "--fail-on-parse-warnings", "--output", output, input])).unwrap_err();
+        assert_eq!(error.to_string(), "1 repeated SUCCESS lines were ignored (--fail-on-parse-warnings)");
+    }
+
+    #[test]
+    fn interpolate_is_rejected_outside_the_grid() {
+        let error = run(&args(&["--interpolate", "--format", "csv"])).unwrap_err();
+        assert_eq!(error.to_string(), "--interpolate only works with --format grid");
+    }
+}
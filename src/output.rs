@@ -0,0 +1,1525 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+    sync::Arc,
+};
+
+use arrow_array::{ArrayRef, BooleanArray, Float32Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow_schema::{Field, Schema};
+use clap::ValueEnum;
+use parquet::arrow::ArrowWriter;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+use crate::{approx_eq, histogram, mean, outliers, profile, runs::CurvePoint, sorted_entries, LinearFit, Stat, Stats, Totals, DEFAULT_ABSOLUTE_ERROR, DEFAULT_RELATIVE_ERROR};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Debug dump followed by tables sorted by successes per mean/median
+    Table,
+    /// One comma-separated row per configuration
+    Csv,
+    /// One tab-separated row per configuration
+    Tsv,
+    /// A population size by generations grid of one stat (see --grid-metric)
+    Grid,
+    /// A Parquet file with a typed, nullable column per stat
+    Parquet,
+    /// A JSON array with an object per configuration
+    Json,
+    /// A self-contained HTML page with a table that sorts when a header is
+    /// clicked
+    Html,
+}
+
+impl Format {
+    fn separator(self) -> Option<&'static str> {
+        match self {
+            Self::Table | Self::Grid | Self::Parquet | Self::Json | Self::Html => None,
+            Self::Csv => Some(","),
+            Self::Tsv => Some("\t"),
+        }
+    }
+}
+
+type Key = (u32, u32);
+
+/// A single output cell. Every format renders cells through `format_value`
+/// so missing values are handled the same way everywhere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Integer(u64),
+    Float(f32),
+    Text(&'static str),
+    Bool(bool),
+    /// A grid cell with no data, estimated from its neighbors by
+    /// `--interpolate`. Written with a trailing `*`.
+    Interpolated(f32),
+    Missing,
+}
+
+impl Value {
+    /// The value as a number, if it is one.
+    pub fn as_f32(self) -> Option<f32> {
+        match self {
+            Self::Integer(i) => Some(i as f32),
+            Self::Float(f) | Self::Interpolated(f) => Some(f),
+            Self::Text(_) | Self::Bool(_) | Self::Missing => None,
+        }
+    }
+}
+
+impl From<Option<f32>> for Value {
+    fn from(value: Option<f32>) -> Self {
+        value.map_or(Self::Missing, Self::Float)
+    }
+}
+
+/// Numbers serialize as numbers, except that NaN and the infinities (which
+/// JSON can't represent) become `null` like missing values.
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Self::Integer(i) => serializer.serialize_u64(i),
+            Self::Float(f) | Self::Interpolated(f) if f.is_finite() => serializer.serialize_f32(f),
+            Self::Text(t) => serializer.serialize_str(t),
+            Self::Bool(b) => serializer.serialize_bool(b),
+            Self::Float(_) | Self::Interpolated(_) | Self::Missing => serializer.serialize_none(),
+        }
+    }
+}
+
+pub fn format_value(value: Value, na_string: &str) -> String {
+    match value {
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Text(t) => t.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Interpolated(f) => format!("{f}*"),
+        Value::Missing => na_string.to_string(),
+    }
+}
+
+/// SI prefixes for `format_compact`, from 10^-12 up to 10^12.
+const SI_PREFIXES: [&str; 9] = ["p", "n", "µ", "m", "", "k", "M", "G", "T"];
+
+/// `value` to three significant digits with an SI suffix, like `1.2k` for 1200
+/// or `56.7µ` for 0.0000567. Zero, NaN, the infinities, and values beyond the
+/// prefixes' range are written as they are.
+pub fn format_compact(value: f32) -> String {
+    let v = f64::from(value);
+    if v == 0.0 || !v.is_finite() {
+        return value.to_string();
+    }
+    let mut exponent = (v.abs().log10() / 3.0).floor() as i32;
+    loop {
+        let Some(prefix) = usize::try_from(exponent + 4).ok().and_then(|i| SI_PREFIXES.get(i)) else {
+            return value.to_string();
+        };
+        let scaled = v / 1000f64.powi(exponent);
+        let decimals = (2 - scaled.abs().log10().floor() as i32).max(0) as usize;
+        let digits = format!("{scaled:.decimals$}");
+        // Rounding can carry into the next prefix, e.g. 999.96 to 1000.
+        if digits.trim_start_matches('-').starts_with("1000") {
+            exponent += 1;
+            continue;
+        }
+        let digits = if digits.contains('.') { digits.trim_end_matches('0').trim_end_matches('.') } else { &digits };
+        return format!("{digits}{prefix}");
+    }
+}
+
+/// Like `format_value`, but with `options.compact` floats are written by
+/// `format_compact`. Integers (counts and keys) are always exact.
+fn format_human(value: Value, options: &OutputOptions) -> String {
+    match value {
+        Value::Float(f) if options.compact => format_compact(f),
+        Value::Interpolated(f) if options.compact => format!("{}*", format_compact(f)),
+        _ => format_value(value, &options.na_string),
+    }
+}
+
+/// Settings shared by all the output formats.
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    pub format: Format,
+    /// Only affects the delimited formats; turned off when appending to a
+    /// file that already has a header.
+    pub include_header: bool,
+    /// What to print for a stat that has no value.
+    pub na_string: String,
+    /// Which of the two configuration key columns comes first.
+    pub key_order: KeyOrder,
+    /// The column to order configurations by, if not by key.
+    pub sort_by: Option<&'static Column>,
+    /// Break `sort_by` ties by key rather than leaving them in an arbitrary
+    /// order.
+    pub sort_stable: bool,
+    /// The stat shown in each cell of the grid format.
+    pub grid_metric: &'static Column,
+    /// Population sizes to use as the grid's axis instead of the ones in the
+    /// data, so grids from different datasets line up.
+    pub pop_values: Option<Vec<u32>>,
+    /// Like `pop_values`, for generations.
+    pub gens_values: Option<Vec<u32>>,
+    /// Add each configuration's run times to the JSON format, and a
+    /// histogram of them to the HTML format.
+    pub include_raw: bool,
+    /// Fill grid cells that have no data from their neighbors.
+    pub interpolate: bool,
+    /// Write floats with SI suffixes (see `format_compact`) in the aligned
+    /// tables and the report.
+    pub compact: bool,
+}
+
+/// The order of the `pop_size` and `num_gens` columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyOrder {
+    #[default]
+    PopGens,
+    GensPop,
+}
+
+impl KeyOrder {
+    /// Puts the population and generations items in this order.
+    pub fn arrange<T>(self, pop: T, gens: T) -> [T; 2] {
+        match self {
+            Self::PopGens => [pop, gens],
+            Self::GensPop => [gens, pop],
+        }
+    }
+}
+
+impl FromStr for KeyOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.replace(' ', "").as_str() {
+            "pop,gens" => Ok(Self::PopGens),
+            "gens,pop" => Ok(Self::GensPop),
+            _ => Err(format!("expected `pop,gens` or `gens,pop`, got `{s}`")),
+        }
+    }
+}
+
+/// Describes one output column: its header, what it means, and how to pull
+/// its value out of a configuration's stats. The delimited writers and the
+/// `--explain` legend are both driven by `COLUMNS`, so they can't disagree.
+pub struct Column {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Which direction is an improvement, for columns where that's clear.
+    pub better: Option<Better>,
+    pub kind: Kind,
+    value: fn(&Key, &Stat) -> Value,
+}
+
+/// The type of a column's values, which sets its type in the Parquet format
+/// no matter which values (if any) are present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A configuration key, stored as `UInt32`.
+    Key,
+    /// A count, stored as `UInt64`.
+    Count,
+    /// A stat, stored as nullable `Float32`.
+    Float,
+    /// A flag, stored as `Boolean`.
+    Flag,
+}
+
+/// Whether lower or higher values of a column are better.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Better {
+    Lower,
+    Higher,
+}
+
+impl Column {
+    pub fn value(&self, key: &Key, stat: &Stat) -> Value {
+        (self.value)(key, stat)
+    }
+}
+
+impl fmt::Debug for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Column").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+/// Looks up a column by name, for options that pick a stat to work with.
+pub fn parse_column(name: &str) -> Result<&'static Column, String> {
+    COLUMNS.iter().find(|c| c.name == name).ok_or_else(|| {
+        let names = COLUMNS.iter().map(|c| c.name).collect::<Vec<_>>();
+        format!("unknown column `{name}`; expected one of {}", names.join(", "))
+    })
+}
+
+/// Like `parse_column`, but only accepts columns with a `better` direction.
+pub fn parse_ranked_column(name: &str) -> Result<&'static Column, String> {
+    let column = parse_column(name)?;
+    match column.better {
+        Some(_) => Ok(column),
+        None => {
+            let names = COLUMNS.iter().filter(|c| c.better.is_some()).map(|c| c.name).collect::<Vec<_>>();
+            Err(format!("column `{name}` has no better direction; expected one of {}", names.join(", ")))
+        }
+    }
+}
+
+pub const COLUMNS: &[Column] = &[
+    Column {
+        name: "pop_size",
+        description: "population size of the configuration",
+        better: None,
+        kind: Kind::Key,
+        value: |(pop_size, _), _| Value::Integer((*pop_size).into()),
+    },
+    Column {
+        name: "num_gens",
+        description: "number of generations of the configuration",
+        better: None,
+        kind: Kind::Key,
+        value: |(_, num_gens), _| Value::Integer((*num_gens).into()),
+    },
+    Column {
+        name: "num_runs",
+        description: "number of runs that reported a run time",
+        better: None,
+        kind: Kind::Count,
+        value: |_, s| Value::Integer(s.result.num_runs as u64),
+    },
+    Column {
+        name: "num_successes",
+        description: "number of SUCCESS entries",
+        better: Some(Better::Higher),
+        kind: Kind::Count,
+        value: |_, s| Value::Integer(s.result.num_successes as u64),
+    },
+    Column {
+        name: "timed_no_success",
+        description: "runs that logged a run time but no SUCCESS",
+        better: None,
+        kind: Kind::Count,
+        value: |_, s| Value::Integer(s.result.timed_no_success as u64),
+    },
+    Column {
+        name: "mean_run_time",
+        description: "sum(run_times) / num_runs",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.mean_run_time),
+    },
+    Column {
+        name: "median_run_time",
+        description: "middle run time; for an even count, the two middle values' mean (see --median-method)",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.median_run_time),
+    },
+    Column {
+        name: "min_run_time",
+        description: "fastest run time",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.min_run_time),
+    },
+    Column {
+        name: "max_run_time",
+        description: "slowest run time",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.max_run_time),
+    },
+    Column {
+        name: "q1_run_time",
+        description: "25th percentile of run times (linear interpolation)",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.q1_run_time),
+    },
+    Column {
+        name: "q3_run_time",
+        description: "75th percentile of run times (linear interpolation)",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.q3_run_time),
+    },
+    Column {
+        name: "std_dev_run_time",
+        description: "sqrt(sum((t - mean_run_time)^2) / (num_runs - 1))",
+        better: None,
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.std_dev_run_time),
+    },
+    Column {
+        name: "sem_run_time",
+        description: "std_dev_run_time / sqrt(num_runs)",
+        better: None,
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.sem_run_time),
+    },
+    Column {
+        name: "risk_adjusted_time",
+        description: "mean_run_time + lambda * std_dev_run_time, with lambda from --risk-lambda",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.risk_adjusted_time),
+    },
+    Column {
+        name: "mean_ci_low",
+        description: "lower bound of the confidence interval for mean_run_time (with --ci-method)",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| s.mean_ci_low.into(),
+    },
+    Column {
+        name: "mean_ci_high",
+        description: "upper bound of the confidence interval for mean_run_time (with --ci-method)",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| s.mean_ci_high.into(),
+    },
+    Column {
+        name: "successes_per_mean",
+        description: "num_successes / mean_run_time",
+        better: Some(Better::Higher),
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.successes_per_mean),
+    },
+    Column {
+        name: "successes_per_median",
+        description: "num_successes / median_run_time",
+        better: Some(Better::Higher),
+        kind: Kind::Float,
+        value: |_, s| Value::Float(s.successes_per_median),
+    },
+    Column {
+        name: "effort_per_success",
+        description: "sum(run_times) / num_successes, the total run time spent per success",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| s.effort_per_success.into(),
+    },
+    Column {
+        name: "auc_success_time",
+        description: "area under the fraction-of-runs-succeeded vs run time curve, normalized to 0-1 by the slowest run",
+        better: Some(Better::Higher),
+        kind: Kind::Float,
+        value: |_, s| s.auc_success_time.into(),
+    },
+    Column {
+        name: "mean_final_fitness",
+        description: "mean final fitness (error) over SUCCESS entries written as `SUCCESS <gen> <fitness>`",
+        better: None,
+        kind: Kind::Float,
+        value: |_, s| s.mean_final_fitness.into(),
+    },
+    Column {
+        name: "median_mean_ratio",
+        description: "median_run_time / mean_run_time; well below 1 means slow outliers (right skew)",
+        better: None,
+        kind: Kind::Float,
+        value: |_, s| s.median_mean_ratio.into(),
+    },
+    Column {
+        name: "success_rate",
+        description: "num_successes / num_runs",
+        better: Some(Better::Higher),
+        kind: Kind::Float,
+        value: |_, s| s.success_rate.into(),
+    },
+    Column {
+        name: "success_rate_ci_low",
+        description: "lower bound of the Wilson score interval for success_rate (see --confidence)",
+        better: Some(Better::Higher),
+        kind: Kind::Float,
+        value: |_, s| s.success_rate_ci_low.into(),
+    },
+    Column {
+        name: "success_rate_ci_high",
+        description: "upper bound of the Wilson score interval for success_rate (see --confidence)",
+        better: Some(Better::Higher),
+        kind: Kind::Float,
+        value: |_, s| s.success_rate_ci_high.into(),
+    },
+    Column {
+        name: "relative_mean",
+        description: "mean_run_time / smallest mean_run_time (only with --normalize mean)",
+        better: Some(Better::Lower),
+        kind: Kind::Float,
+        value: |_, s| s.relative_mean.into(),
+    },
+    Column {
+        name: "formula",
+        description: "the value of --metric-formula",
+        better: None,
+        kind: Kind::Float,
+        value: |_, s| s.formula.into(),
+    },
+    Column {
+        name: "units_normalized",
+        description: "whether any run time had a unit suffix (ms, s, min, h) that was converted to seconds",
+        better: None,
+        kind: Kind::Flag,
+        value: |_, s| Value::Bool(s.result.units_normalized),
+    },
+];
+
+const TABLE_COLUMNS: [&str; 4] = ["pop_size", "num_gens", "successes_per_mean", "successes_per_median"];
+
+/// The columns that the chosen format actually emits.
+pub fn active_columns(options: &OutputOptions) -> Vec<&'static Column> {
+    let (keys, _) = COLUMNS.split_at(2);
+    match (options.format, options.sort_by) {
+        (Format::Table, Some(column)) => keys.iter().chain([column]).collect(),
+        (Format::Table, None) => COLUMNS.iter().filter(|c| TABLE_COLUMNS.contains(&c.name)).collect(),
+        (Format::Grid, _) => keys.iter().chain([options.grid_metric]).collect(),
+        (Format::Csv | Format::Tsv | Format::Parquet | Format::Json | Format::Html, _) => COLUMNS.iter().collect(),
+    }
+}
+
+/// The columns `write_report` lists for each configuration: every one but
+/// the keys, which head each configuration's section instead.
+fn report_columns() -> Vec<&'static Column> {
+    COLUMNS.iter().filter(|c| c.kind != Kind::Key).collect()
+}
+
+/// The name of the extra column `options.include_raw` adds in the JSON and
+/// HTML formats.
+const RAW_COLUMN: &str = "run_times";
+
+/// The description of the `RAW_COLUMN` the chosen format adds, if it adds
+/// one.
+fn raw_column_description(options: &OutputOptions) -> Option<&'static str> {
+    match options.format {
+        _ if !options.include_raw => None,
+        Format::Json => Some("every run time, in input order"),
+        Format::Html => Some("a histogram of the run times"),
+        _ => None,
+    }
+}
+
+/// Writes a short definition of each column the chosen writer emits: the
+/// report's when `report`, otherwise `options.format`'s, along with the raw
+/// run times `options.include_raw` adds.
+pub fn write_legend(out: &mut impl Write, options: &OutputOptions, report: bool) -> io::Result<()> {
+    let (columns, raw) = if report {
+        (report_columns(), None)
+    } else {
+        (active_columns(options), raw_column_description(options))
+    };
+    let entries = columns
+        .iter()
+        .map(|c| (c.name, c.description))
+        .chain(raw.map(|description| (RAW_COLUMN, description)))
+        .collect::<Vec<_>>();
+    let width = entries.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, description) in entries {
+        writeln!(out, "{name:width$}  {description}")?;
+    }
+    writeln!(out)
+}
+
+/// The notice the human-readable formats write instead of an empty table.
+pub const NO_CONFIGURATIONS: &str = "no configurations found";
+
+/// Writes `NO_CONFIGURATIONS` along with how many lines were parsed, so an
+/// empty input can be told apart from one that every line was filtered out of.
+fn write_no_configurations(out: &mut impl Write, lines_parsed: usize) -> io::Result<()> {
+    let lines = if lines_parsed == 1 { "line" } else { "lines" };
+    writeln!(out, "{NO_CONFIGURATIONS} ({lines_parsed} {lines} parsed)")
+}
+
+/// Writes `stats`, computed from `lines_parsed` lines, in `options.format`.
+/// With no configurations, the table and grid formats write a
+/// `NO_CONFIGURATIONS` notice while the machine-readable ones still write a
+/// valid (header-only or empty) document.
+pub fn write_stats(out: &mut impl Write, stats: &Stats, lines_parsed: usize, options: &OutputOptions) -> io::Result<()> {
+    if stats.is_empty() && matches!(options.format, Format::Table | Format::Grid) {
+        return write_no_configurations(out, lines_parsed);
+    }
+    match (options.format, options.format.separator()) {
+        (Format::Grid, _) => write_grid(out, stats, options),
+        (Format::Parquet, _) => write_parquet(out, stats, options),
+        (Format::Json, _) => write_json(out, stats, options),
+        (Format::Html, _) => write_html(out, stats, options),
+        (_, Some(separator)) => write_delimited(out, stats, separator, options),
+        (_, None) => write_table(out, stats, options),
+    }
+}
+
+/// The sorted, distinct values of one key axis, or `forced` if given.
+fn axis_values(stats: &Stats, forced: Option<&Vec<u32>>, axis: fn(&Key) -> u32) -> Vec<u32> {
+    match forced {
+        Some(values) => values.clone(),
+        None => {
+            let values = stats.keys().map(axis).collect::<BTreeSet<_>>();
+            values.into_iter().collect()
+        }
+    }
+}
+
+/// Writes `options.grid_metric` as a grid with a row per population size
+/// and a column per generation count (swapped by `--key-order gens,pop`).
+/// Cells without data get the NA string, or with `options.interpolate` an
+/// estimate from their neighbors where there are any.
+fn write_grid(out: &mut impl Write, stats: &Stats, options: &OutputOptions) -> io::Result<()> {
+    let pops = axis_values(stats, options.pop_values.as_ref(), |&(pop_size, _)| pop_size);
+    let gens = axis_values(stats, options.gens_values.as_ref(), |&(_, num_gens)| num_gens);
+    let [(row_name, row_values), (column_name, column_values)] =
+        options.key_order.arrange(("pop_size", &pops), ("num_gens", &gens));
+
+    let corner = format!("{row_name}\\{column_name}");
+    let column_labels = column_values.iter().map(u32::to_string).collect::<Vec<_>>();
+    let header = [corner.as_str()]
+        .into_iter()
+        .chain(column_labels.iter().map(String::as_str))
+        .collect::<Vec<_>>();
+    let cells = row_values
+        .iter()
+        .map(|&row| {
+            column_values
+                .iter()
+                .map(|&column| {
+                    let key = match options.key_order {
+                        KeyOrder::PopGens => (row, column),
+                        KeyOrder::GensPop => (column, row),
+                    };
+                    stats.get(&key).map(|s| options.grid_metric.value(&key, s))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let rows = row_values
+        .iter()
+        .enumerate()
+        .map(|(r, &row)| {
+            let values = (0..column_values.len()).map(|c| match cells[r][c] {
+                Some(value) => value,
+                None if options.interpolate => interpolate_cell(&cells, row_values, column_values, r, c),
+                None => Value::Missing,
+            });
+            [Value::Integer(row.into())].into_iter().chain(values).collect()
+        })
+        .collect::<Vec<_>>();
+    write_aligned(out, &header, &rows, options)
+}
+
+/// Estimates the empty cell at row `r`, column `c` of `cells` by linearly
+/// interpolating between the nearest numeric cells on either side of it,
+/// along its row and along its column, and averaging the estimates
+/// (bilinear interpolation when all four neighbors are there). Distances come
+/// from the axis values, so uneven spacing is accounted for; axes pinned by
+/// `--pop-values`/`--gens-values` can be in any order, so the distances are
+/// signed. Missing when neither axis has a numeric neighbor on both sides.
+fn interpolate_cell(cells: &[Vec<Option<Value>>], row_values: &[u32], column_values: &[u32], r: usize, c: usize) -> Value {
+    let number = |r: usize, c: usize| cells[r][c].and_then(Value::as_f32).filter(|v| v.is_finite());
+    let between = |at: usize, axis: &[u32], value_at: &dyn Fn(usize) -> Option<f32>| {
+        let (i, low) = (0..at).rev().find_map(|i| value_at(i).map(|v| (i, v)))?;
+        let (j, high) = (at + 1..axis.len()).find_map(|j| value_at(j).map(|v| (j, v)))?;
+        let offset = |k: usize| f64::from(axis[k]) - f64::from(axis[i]);
+        // A repeated axis value puts both neighbors at the same distance.
+        let fraction = if offset(j) == 0.0 { 0.5 } else { offset(at) / offset(j) };
+        Some(low + (high - low) * fraction as f32)
+    };
+    let estimates = [
+        between(c, column_values, &|j| number(r, j)),
+        between(r, row_values, &|i| number(i, c)),
+    ];
+    let estimates = estimates.into_iter().flatten().collect::<Vec<_>>();
+    if estimates.is_empty() {
+        Value::Missing
+    } else {
+        Value::Interpolated(mean(&estimates))
+    }
+}
+
+/// `COLUMNS` with the two leading key columns in `key_order`.
+fn ordered_columns(key_order: KeyOrder) -> Vec<&'static Column> {
+    let (keys, rest) = COLUMNS.split_at(2);
+    key_order.arrange(&keys[0], &keys[1]).into_iter().chain(rest).collect()
+}
+
+/// Sorts `entries` by `column`, ascending. Missing and NaN values count as
+/// equal to each other and come after every number. When `stable`, ties of
+/// either kind fall back to key order, so the result doesn't depend on the
+/// order `entries` started in; otherwise they're left in whatever order the
+/// (faster) unstable sort produces.
+fn sort_by_column(entries: &mut [(&Key, &Stat)], column: &Column, stable: bool) {
+    let number = |key, s| column.value(key, s).as_f32().filter(|v| !v.is_nan());
+    let by_value = |a_key, a, b_key, b| match (number(a_key, a), number(b_key, b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    };
+    profile::time("sort", || {
+        if stable {
+            entries.sort_by(|(a_key, a), (b_key, b)| by_value(a_key, a, b_key, b).then_with(|| a_key.cmp(b_key)));
+        } else {
+            entries.sort_unstable_by(|(a_key, a), (b_key, b)| by_value(a_key, a, b_key, b));
+        }
+    });
+}
+
+/// The configuration with the best value of `column`, ignoring missing and
+/// NaN values. Ties (within the default `approx_eq` tolerances) go to the
+/// smallest key, so the pick is deterministic.
+pub fn best_config(stats: &Stats, column: &Column) -> Option<Key> {
+    let better = column.better?;
+    let mut best: Option<(Key, f32)> = None;
+    for (key, s) in stats {
+        let Some(value) = column.value(key, s).as_f32().filter(|v| !v.is_nan()) else {
+            continue;
+        };
+        let improves = best.is_none_or(|(_, best_value)| {
+            let beats = match better {
+                Better::Lower => value < best_value,
+                Better::Higher => value > best_value,
+            };
+            beats && !approx_eq(value, best_value, DEFAULT_RELATIVE_ERROR, DEFAULT_ABSOLUTE_ERROR)
+        });
+        if improves {
+            best = Some((*key, value));
+        }
+    }
+    best.map(|(key, _)| key)
+}
+
+/// Writes a configuration as `pop_size=<pop>` and `num_gens=<gens>` lines,
+/// which a shell script can `eval` or source.
+pub fn write_assignments(out: &mut impl Write, (pop_size, num_gens): Key, key_order: KeyOrder) -> io::Result<()> {
+    for (name, value) in key_order.arrange(("pop_size", pop_size), ("num_gens", num_gens)) {
+        writeln!(out, "{name}={value}")?;
+    }
+    Ok(())
+}
+
+fn write_delimited(out: &mut impl Write, stats: &Stats, separator: &str, options: &OutputOptions) -> io::Result<()> {
+    let columns = ordered_columns(options.key_order);
+    let header = columns.iter().map(|c| c.name).collect::<Vec<_>>();
+    let rows = ordered_entries(stats, options)
+        .into_iter()
+        .map(|(key, s)| columns.iter().map(|c| (c.value)(key, s)).collect())
+        .collect::<Vec<_>>();
+    write_separated(out, &header, &rows, separator, options)
+}
+
+/// The configurations in output order: by key, or by `options.sort_by`.
+fn ordered_entries<'a>(stats: &'a Stats, options: &OutputOptions) -> Vec<(&'a Key, &'a Stat)> {
+    let mut entries = sorted_entries(stats);
+    if let Some(column) = options.sort_by {
+        sort_by_column(&mut entries, column, options.sort_stable);
+    }
+    entries
+}
+
+/// Writes the same columns as the delimited formats as a Parquet file, each
+/// typed by its `Kind`.
+fn write_parquet(out: &mut impl Write, stats: &Stats, options: &OutputOptions) -> io::Result<()> {
+    let entries = ordered_entries(stats, options);
+    let columns = ordered_columns(options.key_order);
+    let (fields, arrays): (Vec<_>, Vec<_>) = columns
+        .iter()
+        .map(|column| {
+            let values = entries.iter().map(|(key, s)| column.value(key, s)).collect::<Vec<_>>();
+            parquet_column(column, &values)
+        })
+        .unzip();
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).map_err(io::Error::other)?;
+
+    // The writer needs to own a `Send` sink, so buffer the file and copy it out.
+    let mut file = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut file, batch.schema(), None).map_err(io::Error::other)?;
+    writer.write(&batch).map_err(io::Error::other)?;
+    writer.close().map_err(io::Error::other)?;
+    out.write_all(&file)
+}
+
+fn parquet_column(column: &Column, values: &[Value]) -> (Field, ArrayRef) {
+    let integer = |v: &Value| match v {
+        Value::Integer(i) => Some(*i),
+        _ => None,
+    };
+    let array: ArrayRef = match column.kind {
+        Kind::Key => Arc::new(values.iter().map(|v| integer(v).and_then(|i| u32::try_from(i).ok())).collect::<UInt32Array>()),
+        Kind::Count => Arc::new(values.iter().map(integer).collect::<UInt64Array>()),
+        Kind::Flag => Arc::new(
+            values
+                .iter()
+                .map(|v| match v {
+                    Value::Bool(b) => Some(*b),
+                    _ => None,
+                })
+                .collect::<BooleanArray>(),
+        ),
+        Kind::Float => Arc::new(values.iter().map(|v| v.as_f32()).collect::<Float32Array>()),
+    };
+    let field = Field::new(column.name, array.data_type().clone(), column.kind != Kind::Key);
+    (field, array)
+}
+
+/// One configuration's object in the JSON format.
+struct JsonRow<'a> {
+    key: &'a Key,
+    stat: &'a Stat,
+    columns: &'a [&'static Column],
+    include_raw: bool,
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for column in self.columns {
+            map.serialize_entry(column.name, &column.value(self.key, self.stat))?;
+        }
+        if self.include_raw {
+            map.serialize_entry(RAW_COLUMN, &self.stat.result.run_times)?;
+        }
+        map.end()
+    }
+}
+
+/// Writes a JSON array with an object per configuration, holding the same
+/// columns as the delimited formats (in the same order), plus the raw
+/// `run_times` with `options.include_raw`.
+fn write_json(out: &mut impl Write, stats: &Stats, options: &OutputOptions) -> io::Result<()> {
+    let columns = ordered_columns(options.key_order);
+    let rows = ordered_entries(stats, options)
+        .into_iter()
+        .map(|(key, stat)| JsonRow {
+            key,
+            stat,
+            columns: &columns,
+            include_raw: options.include_raw,
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_writer_pretty(&mut *out, &rows)?;
+    writeln!(out)
+}
+
+/// Bars in each configuration's run time histogram in the HTML format.
+const HTML_HISTOGRAM_BINS: usize = 20;
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 2px 6px; text-align: right; }
+th { background: #eee; cursor: pointer; }
+svg { display: block; }";
+
+/// Sorts the table by a column when its header is clicked, numerically when
+/// both cells are numbers, toggling the direction on repeated clicks.
+const HTML_SCRIPT: &str = "document.querySelectorAll('th').forEach((th, column) => {
+  th.addEventListener('click', () => {
+    const body = th.closest('table').tBodies[0];
+    const ascending = th.dataset.order !== 'asc';
+    th.dataset.order = ascending ? 'asc' : 'desc';
+    const value = row => row.cells[column].textContent;
+    const rows = Array.from(body.rows).sort((a, b) => {
+      const [x, y] = [value(a), value(b)];
+      const [m, n] = [parseFloat(x), parseFloat(y)];
+      const order = isNaN(m) || isNaN(n) ? x.localeCompare(y) : m - n;
+      return ascending ? order : -order;
+    });
+    rows.forEach(row => body.appendChild(row));
+  });
+});";
+
+/// Writes a single HTML page with no external dependencies: a table of the
+/// same columns as the delimited formats (with a run time histogram per
+/// configuration when `options.include_raw`), sortable by clicking a header.
+fn write_html(out: &mut impl Write, stats: &Stats, options: &OutputOptions) -> io::Result<()> {
+    let columns = ordered_columns(options.key_order);
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Run time stats</title>")?;
+    writeln!(out, "<style>\n{HTML_STYLE}\n</style>\n</head>\n<body>\n<table>\n<thead>\n<tr>")?;
+    for column in &columns {
+        writeln!(out, "<th title=\"{}\">{}</th>", escape_html(column.description), column.name)?;
+    }
+    if options.include_raw {
+        writeln!(out, "<th>{RAW_COLUMN}</th>")?;
+    }
+    writeln!(out, "</tr>\n</thead>\n<tbody>")?;
+    for (key, s) in ordered_entries(stats, options) {
+        write!(out, "<tr>")?;
+        for column in &columns {
+            write!(out, "<td>{}</td>", escape_html(&format_value(column.value(key, s), &options.na_string)))?;
+        }
+        if options.include_raw {
+            write!(out, "<td>{}</td>", histogram_svg(&histogram(&s.result.run_times, HTML_HISTOGRAM_BINS)))?;
+        }
+        writeln!(out, "</tr>")?;
+    }
+    writeln!(out, "</tbody>\n</table>\n<script>\n{HTML_SCRIPT}\n</script>\n</body>\n</html>")
+}
+
+/// An inline SVG bar chart of `counts`, scaled to the largest.
+fn histogram_svg(counts: &[u64]) -> String {
+    const BAR_WIDTH: usize = 4;
+    const HEIGHT: u64 = 20;
+    let tallest = counts.iter().copied().max().unwrap_or(0).max(1);
+    let bars = counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let height = count * HEIGHT / tallest;
+            format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{height}\"/>", i * BAR_WIDTH, HEIGHT - height, BAR_WIDTH - 1)
+        })
+        .collect::<String>();
+    format!("<svg width=\"{}\" height=\"{HEIGHT}\">{bars}</svg>", counts.len() * BAR_WIDTH)
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn write_separated(
+    out: &mut impl Write,
+    header: &[&str],
+    rows: &[Vec<Value>],
+    separator: &str,
+    options: &OutputOptions,
+) -> io::Result<()> {
+    if options.include_header {
+        writeln!(out, "{}", header.join(separator))?;
+    }
+    for row in rows {
+        let fields = row.iter().map(|&v| format_value(v, &options.na_string)).collect::<Vec<_>>();
+        writeln!(out, "{}", fields.join(separator))?;
+    }
+    Ok(())
+}
+
+/// Writes one row per entry of `rows` in the requested format (aligned
+/// columns for `Table`, otherwise delimited), each starting with its
+/// configuration's key columns in `options.key_order`.
+pub fn write_keyed(out: &mut impl Write, header: &[&str], rows: &[(Key, Vec<Value>)], options: &OutputOptions) -> io::Result<()> {
+    let full_header = options
+        .key_order
+        .arrange("pop_size", "num_gens")
+        .into_iter()
+        .chain(header.iter().copied())
+        .collect::<Vec<_>>();
+    let full_rows = rows
+        .iter()
+        .map(|(key, values)| key_values(*key, options.key_order).into_iter().chain(values.iter().copied()).collect())
+        .collect::<Vec<_>>();
+    write_rows(out, &full_header, &full_rows, options)
+}
+
+/// Writes `rows` under `header` in the requested format, like `write_keyed`
+/// but without key columns.
+pub fn write_rows(out: &mut impl Write, header: &[&str], rows: &[Vec<Value>], options: &OutputOptions) -> io::Result<()> {
+    match options.format.separator() {
+        None => write_aligned(out, header, rows, options),
+        Some(separator) => write_separated(out, header, rows, separator, options),
+    }
+}
+
+fn key_values((pop_size, num_gens): Key, key_order: KeyOrder) -> [Value; 2] {
+    key_order.arrange(Value::Integer(pop_size.into()), Value::Integer(num_gens.into()))
+}
+
+fn write_table(out: &mut impl Write, stats: &Stats, options: &OutputOptions) -> io::Result<()> {
+    writeln!(out, "{stats:?}")?;
+
+    let metrics = match options.sort_by {
+        Some(column) => vec![(column.name, column)],
+        None => vec![
+            ("SuccessesPerMean", named_column("successes_per_mean")),
+            ("SuccessesPerMedian", named_column("successes_per_median")),
+        ],
+    };
+    let mut pairs = sorted_entries(stats);
+    for (name, column) in metrics {
+        writeln!(out)?;
+        sort_by_column(&mut pairs, column, options.sort_stable);
+        let rows = pairs
+            .iter()
+            .map(|(key, s)| {
+                let mut row = key_values(**key, options.key_order).to_vec();
+                row.push(column.value(key, s));
+                row
+            })
+            .collect::<Vec<_>>();
+        let [first, second] = options.key_order.arrange("PopSize", "NumGens");
+        write_aligned(out, &[first, second, name], &rows, options)?;
+    }
+
+    Ok(())
+}
+
+fn named_column(name: &str) -> &'static Column {
+    parse_column(name).expect("built-in column names are valid")
+}
+
+/// Writes `rows` under `header` with every column padded to its widest
+/// cell. Numeric (and missing) cells are right-aligned, text left-aligned.
+fn write_aligned(out: &mut impl Write, header: &[&str], rows: &[Vec<Value>], options: &OutputOptions) -> io::Result<()> {
+    let cells = rows
+        .iter()
+        .map(|row| row.iter().map(|&v| format_human(v, options)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let widths = header
+        .iter()
+        .enumerate()
+        .map(|(i, h)| cells.iter().map(|row| row[i].chars().count()).fold(h.chars().count(), usize::max))
+        .collect::<Vec<_>>();
+
+    let left_aligned = (0..header.len())
+        .map(|i| rows.iter().any(|row| matches!(row[i], Value::Text(_) | Value::Bool(_))))
+        .collect::<Vec<_>>();
+    let pad = |cell: &str, i: usize| {
+        let width = widths[i];
+        if left_aligned[i] {
+            format!("{cell:<width$}")
+        } else {
+            format!("{cell:>width$}")
+        }
+    };
+
+    let header_line = header.iter().enumerate().map(|(i, h)| pad(h, i)).collect::<Vec<_>>();
+    writeln!(out, "{}", header_line.join("  ").trim_end())?;
+    for row in &cells {
+        let line = row.iter().enumerate().map(|(i, cell)| pad(cell, i)).collect::<Vec<_>>();
+        writeln!(out, "{}", line.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+/// Bars in each `write_report` histogram.
+const REPORT_HISTOGRAM_BINS: usize = 10;
+
+/// The widest histogram bar `write_report` draws.
+const REPORT_BAR_WIDTH: usize = 40;
+
+/// Writes a multi-line section per configuration, in key order or by
+/// `options.sort_by`: every column, the outlying run times, and a histogram
+/// of the run times. Like the table format, it writes a `NO_CONFIGURATIONS`
+/// notice (with `lines_parsed`) when there are no configurations.
+pub fn write_report(out: &mut impl Write, stats: &Stats, lines_parsed: usize, options: &OutputOptions) -> io::Result<()> {
+    if stats.is_empty() {
+        return write_no_configurations(out, lines_parsed);
+    }
+    let columns = report_columns();
+    let width = columns.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    for (i, (key, s)) in ordered_entries(stats, options).into_iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        let [first, second] = options.key_order.arrange(format!("pop_size {}", key.0), format!("num_gens {}", key.1));
+        writeln!(out, "{first}, {second}")?;
+        for column in &columns {
+            writeln!(out, "  {:width$}  {}", column.name, format_human(column.value(key, s), options))?;
+        }
+
+        let outlying = outliers(&s.result.run_times);
+        let outlying = if outlying.is_empty() {
+            "none".to_string()
+        } else {
+            outlying.iter().map(|&v| format_human(Value::Float(v), options)).collect::<Vec<_>>().join(", ")
+        };
+        writeln!(out, "  {:width$}  {outlying}", "outliers")?;
+
+        let bins = if s.max_run_time > s.min_run_time { REPORT_HISTOGRAM_BINS } else { 1 };
+        let counts = histogram(&s.result.run_times, bins);
+        if counts.is_empty() {
+            continue;
+        }
+        writeln!(out, "  histogram")?;
+        let bin_width = (s.max_run_time - s.min_run_time) / counts.len() as f32;
+        let tallest = counts.iter().copied().max().unwrap_or(0).max(1);
+        let labels = (0..counts.len())
+            .map(|bin| format_human(Value::Float(s.min_run_time + bin as f32 * bin_width), options))
+            .collect::<Vec<_>>();
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+        for (label, count) in labels.iter().zip(counts) {
+            let bar = "#".repeat((count as usize * REPORT_BAR_WIDTH).div_ceil(tallest as usize));
+            writeln!(out, "    {label:>label_width$}  {:<REPORT_BAR_WIDTH$}  {count}", bar)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `totals` as a one-row aligned table after a blank line, for
+/// following the stats in the table and grid formats.
+pub fn write_totals(out: &mut impl Write, totals: &Totals, options: &OutputOptions) -> io::Result<()> {
+    writeln!(out)?;
+    let row = vec![
+        Value::Integer(totals.num_runs as u64),
+        Value::Integer(totals.num_successes as u64),
+        totals.success_rate().into(),
+        Value::Float(totals.total_run_time),
+    ];
+    write_aligned(
+        out,
+        &["total_runs", "total_successes", "overall_success_rate", "total_run_time"],
+        &[row],
+        options,
+    )
+}
+
+pub fn write_learning_curve(out: &mut impl Write, curve: &[CurvePoint], include_header: bool) -> io::Result<()> {
+    if include_header {
+        writeln!(out, "n,mean_estimate,success_rate_estimate")?;
+    }
+    for point in curve {
+        writeln!(out, "{},{},{}", point.n, point.mean_estimate, point.success_rate_estimate)?;
+    }
+    Ok(())
+}
+
+pub fn write_fit(out: &mut impl Write, pop_size: u32, fit: &LinearFit) -> io::Result<()> {
+    writeln!(out, "mean_run_time ~ num_gens for pop_size {pop_size}")?;
+    writeln!(out, "slope = {}", fit.slope)?;
+    writeln!(out, "intercept = {}", fit.intercept)?;
+    writeln!(out, "r_squared = {}", fit.r_squared)
+}
+
+pub fn write_welch(out: &mut impl Write, a: Key, b: Key, t: f64, df: f64, p: f64) -> io::Result<()> {
+    writeln!(out, "Welch's t-test: {}:{} vs {}:{}", a.0, a.1, b.0, b.1)?;
+    writeln!(out, "t = {t}")?;
+    writeln!(out, "df = {df}")?;
+    writeln!(out, "p = {p}")
+}
+
+pub fn write_sample_size(out: &mut impl Write, effect: f64, power: f64, runs: f64) -> io::Result<()> {
+    writeln!(out, "runs per configuration to detect a difference of {effect} at {power} power = {runs}")
+}
+
+pub fn write_prob_faster(out: &mut impl Write, a: Key, b: Key, prob: f64) -> io::Result<()> {
+    writeln!(out, "P({}:{} run faster than {}:{} run) = {prob}", a.0, a.1, b.0, b.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data_to_stats, Data, MedianMethod, Result, StatsOptions};
+
+    fn result(run_times: &[f32], num_successes: usize) -> Result {
+        Result {
+            num_runs: run_times.len(),
+            num_successes,
+            run_times: run_times.to_vec(),
+            ..Result::default()
+        }
+    }
+
+    /// Stats for configurations where every run succeeded.
+    fn stats(configs: &[(Key, &[f32])]) -> Stats {
+        stats_of(configs.iter().map(|&(key, run_times)| (key, result(run_times, run_times.len()))).collect())
+    }
+
+    fn stats_of(data: Data) -> Stats {
+        let options = StatsOptions {
+            confidence: 0.95,
+            ci_method: None,
+            resamples: 1000,
+            seed: Some(0),
+            median_method: MedianMethod::Average,
+            risk_lambda: 1.0,
+        };
+        data_to_stats(data, &options)
+    }
+
+    fn options(format: Format) -> OutputOptions {
+        OutputOptions {
+            format,
+            include_header: true,
+            na_string: "NA".to_string(),
+            key_order: KeyOrder::PopGens,
+            sort_by: None,
+            sort_stable: false,
+            grid_metric: named_column("mean_run_time"),
+            pop_values: None,
+            gens_values: None,
+            include_raw: false,
+            interpolate: false,
+            compact: false,
+        }
+    }
+
+    fn output_bytes(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> Vec<u8> {
+        let mut out = Vec::new();
+        write(&mut out).unwrap();
+        out
+    }
+
+    fn output(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+        String::from_utf8(output_bytes(write)).unwrap()
+    }
+
+    #[test]
+    fn the_legend_defines_each_active_column() {
+        for format in [Format::Table, Format::Grid, Format::Csv] {
+            let options = options(format);
+            let legend = output(|out| write_legend(out, &options, false));
+            let names = legend.lines().filter_map(|line| line.split_whitespace().next()).collect::<Vec<_>>();
+            let expected = active_columns(&options).iter().map(|c| c.name).collect::<Vec<_>>();
+            assert_eq!(names, expected);
+        }
+        assert_eq!(active_columns(&options(Format::Csv)).len(), COLUMNS.len());
+    }
+
+    #[test]
+    fn the_legend_matches_what_the_report_and_include_raw_write() {
+        let names = |legend: String| {
+            legend.lines().filter_map(|line| line.split_whitespace().next()).map(String::from).collect::<Vec<_>>()
+        };
+
+        let stats = stats(&[((100, 50), &[1.0, 2.0])]);
+        let report = output(|out| write_report(out, &stats, 2, &options(Format::Table)));
+        let legend = names(output(|out| write_legend(out, &options(Format::Table), true)));
+        assert_eq!(legend.len(), COLUMNS.len() - 2);
+        for name in &legend {
+            assert!(report.lines().any(|line| line.split_whitespace().next() == Some(name.as_str())), "{name}");
+        }
+
+        for format in [Format::Json, Format::Html] {
+            let options = OutputOptions {
+                include_raw: true,
+                ..options(format)
+            };
+            let legend = names(output(|out| write_legend(out, &options, false)));
+            assert_eq!(legend.last().map(String::as_str), Some(RAW_COLUMN));
+            let without_raw = OutputOptions { include_raw: false, ..options };
+            assert!(!names(output(|out| write_legend(out, &without_raw, false))).contains(&RAW_COLUMN.to_string()));
+        }
+        let csv = OutputOptions {
+            include_raw: true,
+            ..options(Format::Csv)
+        };
+        assert_eq!(names(output(|out| write_legend(out, &csv, false))).len(), COLUMNS.len());
+    }
+
+    #[test]
+    fn entries_are_in_key_order_every_time() {
+        let stats = stats(&[((200, 10), &[1.0]), ((100, 50), &[2.0]), ((100, 10), &[3.0]), ((300, 5), &[4.0])]);
+        let options = options(Format::Csv);
+        let keys = || ordered_entries(&stats, &options).into_iter().map(|(&key, _)| key).collect::<Vec<_>>();
+        let expected = vec![(100, 10), (100, 50), (200, 10), (300, 5)];
+        assert_eq!(keys(), expected);
+        assert_eq!(keys(), expected);
+
+        let csv = output(|out| write_stats(out, &stats, 4, &options));
+        let rows = csv.lines().skip(1).map(|line| line.split(',').take(2).collect::<Vec<_>>().join(":")).collect::<Vec<_>>();
+        assert_eq!(rows, ["100:10", "100:50", "200:10", "300:5"]);
+    }
+
+    #[test]
+    fn missing_values_use_the_na_string_except_in_json() {
+        assert_eq!(format_value(Value::Missing, "-"), "-");
+        assert_eq!(format_value(Value::from(None), "n/a"), "n/a");
+        assert_eq!(format_value(Value::from(Some(1.5)), "n/a"), "1.5");
+
+        let stats = stats(&[((100, 50), &[1.0, 2.0])]);
+        for (format, separator) in [(Format::Csv, ','), (Format::Tsv, '\t')] {
+            let options = OutputOptions {
+                na_string: "n/a".to_string(),
+                ..options(format)
+            };
+            let text = output(|out| write_stats(out, &stats, 2, &options));
+            let mut lines = text.lines().map(|line| line.split(separator).collect::<Vec<_>>());
+            let (header, row) = (lines.next().unwrap(), lines.next().unwrap());
+            let field = |name| row[header.iter().position(|&h| h == name).unwrap()];
+            assert_eq!(field("mean_ci_low"), "n/a");
+            assert_eq!(field("relative_mean"), "n/a");
+            assert_eq!(field("mean_run_time"), "1.5");
+        }
+
+        let options = OutputOptions {
+            na_string: "n/a".to_string(),
+            ..options(Format::Json)
+        };
+        let json = output(|out| write_stats(out, &stats, 2, &options));
+        let rows = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(rows[0]["mean_ci_low"], serde_json::Value::Null);
+        assert_eq!(rows[0]["mean_run_time"], 1.5);
+    }
+
+    #[test]
+    fn aligned_columns_pad_to_the_widest_cell() {
+        let rows = [
+            vec![Value::Integer(5), Value::Float(1.25), Value::Text("fast")],
+            vec![Value::Integer(1000), Value::Missing, Value::Text("slower")],
+        ];
+        let table = output(|out| write_aligned(out, &["pop", "mean_run_time", "label"], &rows, &options(Format::Table)));
+        assert_eq!(
+            table.lines().collect::<Vec<_>>(),
+            [" pop  mean_run_time  label", "   5           1.25  fast", "1000             NA  slower"]
+        );
+    }
+
+    #[test]
+    fn gens_pop_swaps_the_key_columns() {
+        let stats = stats(&[((100, 50), &[1.0])]);
+        let options = OutputOptions {
+            key_order: "gens, pop".parse().unwrap(),
+            ..options(Format::Csv)
+        };
+        let csv = output(|out| write_stats(out, &stats, 1, &options));
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("num_gens,pop_size,num_runs,"));
+        assert!(lines.next().unwrap().starts_with("50,100,1,"));
+
+        let json = output(|out| write_stats(out, &stats, 1, &OutputOptions { format: Format::Json, ..options.clone() }));
+        let rows = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!((rows[0]["pop_size"].as_u64(), rows[0]["num_gens"].as_u64()), (Some(100), Some(50)));
+
+        assert!("pop".parse::<KeyOrder>().is_err());
+    }
+
+    #[test]
+    fn a_pinned_axis_value_without_data_is_na() {
+        let stats = stats(&[((100, 50), &[1.0]), ((500, 50), &[2.0])]);
+        let options = OutputOptions {
+            pop_values: Some(vec![100, 250, 500]),
+            gens_values: Some(vec![50, 75]),
+            ..options(Format::Grid)
+        };
+        let grid = output(|out| write_stats(out, &stats, 2, &options));
+        let cells = grid.lines().map(|line| line.split_whitespace().collect::<Vec<_>>()).collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            [
+                vec!["pop_size\\num_gens", "50", "75"],
+                vec!["100", "1", "NA"],
+                vec!["250", "NA", "NA"],
+                vec!["500", "2", "NA"],
+            ]
+        );
+    }
+
+    #[test]
+    fn best_picks_the_winner_and_writes_assignments() {
+        let effort = named_column("effort_per_success");
+        let stats = stats_of(Data::from([
+            ((100, 50), result(&[4.0, 6.0], 1)),
+            ((200, 50), result(&[3.0, 3.0], 2)),
+            ((300, 50), result(&[1.0], 0)),
+            // Successes without any run times have no effort per success, so
+            // they can't look free.
+            ((400, 50), result(&[], 3)),
+        ]));
+        assert_eq!(best_config(&stats, effort), Some((200, 50)));
+        assert_eq!(best_config(&stats, named_column("success_rate")), Some((200, 50)));
+        assert_eq!(best_config(&stats, named_column("units_normalized")), None);
+
+        // An exact tie goes to the smaller key.
+        let tied = stats_of(Data::from([((500, 50), result(&[2.0], 1)), ((200, 100), result(&[2.0], 1))]));
+        assert_eq!(best_config(&tied, effort), Some((200, 100)));
+
+        let assignments = output(|out| write_assignments(out, (200, 50), KeyOrder::PopGens));
+        assert_eq!(assignments, "pop_size=200\nnum_gens=50\n");
+        let assignments = output(|out| write_assignments(out, (200, 50), KeyOrder::GensPop));
+        assert_eq!(assignments, "num_gens=50\npop_size=200\n");
+    }
+
+    fn read_parquet(bytes: &[u8]) -> (Arc<Schema>, Vec<RecordBatch>) {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(bytes).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        let schema = builder.schema().clone();
+        (schema, builder.build().unwrap().collect::<std::result::Result<Vec<_>, _>>().unwrap())
+    }
+
+    #[test]
+    fn parquet_reads_back_with_typed_columns() {
+        use arrow_array::{
+            cast::AsArray,
+            types::{Float32Type, UInt32Type, UInt64Type},
+            Array,
+        };
+        use arrow_schema::DataType;
+
+        let options = options(Format::Parquet);
+        for stats in [stats(&[((100, 50), &[1.0, 2.0]), ((200, 50), &[3.0])]), Stats::new()] {
+            let (schema, batches) = read_parquet(&output_bytes(|out| write_stats(out, &stats, 3, &options)));
+            assert_eq!(schema.fields().len(), COLUMNS.len());
+            for (field, column) in schema.fields().iter().zip(COLUMNS) {
+                let data_type = match column.kind {
+                    Kind::Key => DataType::UInt32,
+                    Kind::Count => DataType::UInt64,
+                    Kind::Float => DataType::Float32,
+                    Kind::Flag => DataType::Boolean,
+                };
+                assert_eq!((field.name().as_str(), field.data_type()), (column.name, &data_type));
+                assert_eq!(field.is_nullable(), column.kind != Kind::Key);
+            }
+            assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), stats.len());
+        }
+
+        let stats = stats(&[((100, 50), &[1.0, 2.0]), ((200, 50), &[3.0])]);
+        let (_, batches) = read_parquet(&output_bytes(|out| write_stats(out, &stats, 3, &options)));
+        let batch = &batches[0];
+        let column = |name| batch.column_by_name(name).unwrap();
+        assert_eq!(column("pop_size").as_primitive::<UInt32Type>().values(), &[100, 200]);
+        assert_eq!(column("num_runs").as_primitive::<UInt64Type>().values(), &[2, 1]);
+        assert_eq!(column("mean_run_time").as_primitive::<Float32Type>().values(), &[1.5, 3.0]);
+        assert_eq!(column("mean_ci_low").null_count(), 2);
+    }
+
+    #[test]
+    fn the_report_labels_each_stat() {
+        let stats = stats(&[((100, 50), &[1.0, 2.0, 3.0, 100.0])]);
+        let report = output(|out| write_report(out, &stats, 4, &options(Format::Table)));
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "pop_size 100, num_gens 50");
+        let value = |label: &str| {
+            let line = lines.iter().find(|line| line.split_whitespace().next() == Some(label)).unwrap();
+            line.split_whitespace().skip(1).collect::<Vec<_>>().join(" ")
+        };
+        assert_eq!(value("mean_run_time"), "26.5");
+        assert_eq!(value("median_run_time"), "2.5");
+        assert_eq!(value("num_runs"), "4");
+        assert_eq!(value("outliers"), "100");
+        assert!(lines.contains(&"  histogram"));
+    }
+
+    #[test]
+    fn nan_metrics_sort_last_in_key_order() {
+        // Neither of the configurations without run times has a mean.
+        let stats = stats(&[((300, 50), &[]), ((200, 50), &[5.0]), ((100, 50), &[]), ((400, 50), &[1.0])]);
+        let column = named_column("mean_run_time");
+        let mut entries = stats.iter().rev().collect::<Vec<_>>();
+        sort_by_column(&mut entries, column, true);
+        let keys = entries.iter().map(|(&key, _)| key).collect::<Vec<_>>();
+        assert_eq!(keys, [(400, 50), (200, 50), (100, 50), (300, 50)]);
+    }
+
+    #[test]
+    fn json_run_times_are_only_included_on_request() {
+        let stats = stats(&[((100, 50), &[1.5, 2.5])]);
+        let json = |include_raw| {
+            let options = OutputOptions {
+                include_raw,
+                ..options(Format::Json)
+            };
+            serde_json::from_str::<serde_json::Value>(&output(|out| write_stats(out, &stats, 2, &options))).unwrap()
+        };
+        assert_eq!(json(true)[0]["run_times"], serde_json::json!([1.5, 2.5]));
+        assert!(json(false)[0].get("run_times").is_none());
+        assert_eq!(json(false)[0]["num_runs"], 2);
+    }
+
+    #[test]
+    fn no_configurations_still_gives_valid_output() {
+        let empty = Stats::new();
+        for format in [Format::Table, Format::Grid] {
+            let text = output(|out| write_stats(out, &empty, 12, &options(format)));
+            assert_eq!(text, format!("{NO_CONFIGURATIONS} (12 lines parsed)\n"));
+        }
+        let report = output(|out| write_report(out, &empty, 1, &options(Format::Table)));
+        assert_eq!(report, format!("{NO_CONFIGURATIONS} (1 line parsed)\n"));
+
+        let csv = output(|out| write_stats(out, &empty, 0, &options(Format::Csv)));
+        assert_eq!(csv.lines().collect::<Vec<_>>(), [COLUMNS.iter().map(|c| c.name).collect::<Vec<_>>().join(",")]);
+        let json = output(|out| write_stats(out, &empty, 0, &options(Format::Json)));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&json).unwrap(), serde_json::json!([]));
+        let html = output(|out| write_stats(out, &empty, 0, &options(Format::Html)));
+        assert!(html.contains("<tbody>\n</tbody>"));
+    }
+
+    #[test]
+    fn interpolation_fills_a_hole_from_its_neighbors() {
+        // 200:20 is missing; its row neighbors average 3 and its column
+        // neighbors 4.
+        let stats = stats(&[
+            ((100, 10), &[1.0]),
+            ((100, 20), &[1.0]),
+            ((100, 30), &[1.0]),
+            ((200, 10), &[2.0]),
+            ((200, 30), &[4.0]),
+            ((300, 10), &[1.0]),
+            ((300, 20), &[7.0]),
+            ((300, 30), &[1.0]),
+        ]);
+        let cell = |options: &OutputOptions| {
+            let grid = output(|out| write_stats(out, &stats, 8, options));
+            let row = grid.lines().find(|line| line.trim_start().starts_with("200")).unwrap().to_string();
+            row.split_whitespace().nth(2).unwrap().to_string()
+        };
+        let interpolate = OutputOptions {
+            interpolate: true,
+            ..options(Format::Grid)
+        };
+        assert_eq!(cell(&interpolate), "3.5*");
+        assert_eq!(cell(&options(Format::Grid)), "NA");
+
+        // Pinned axes can run in either direction.
+        let descending = OutputOptions {
+            pop_values: Some(vec![300, 200, 100]),
+            gens_values: Some(vec![30, 20, 10]),
+            ..interpolate
+        };
+        assert_eq!(cell(&descending), "3.5*");
+    }
+
+    #[test]
+    fn stable_sorting_breaks_ties_by_key() {
+        let stats = stats(&[((300, 50), &[2.0]), ((100, 50), &[2.0]), ((200, 10), &[1.0]), ((200, 50), &[2.0])]);
+        let column = named_column("mean_run_time");
+        for start in [stats.iter().collect::<Vec<_>>(), stats.iter().rev().collect()] {
+            let mut entries = start;
+            sort_by_column(&mut entries, column, true);
+            let keys = entries.iter().map(|(&key, _)| key).collect::<Vec<_>>();
+            assert_eq!(keys, [(200, 10), (100, 50), (200, 50), (300, 50)]);
+        }
+    }
+
+    #[test]
+    fn html_has_a_row_per_configuration() {
+        let stats = stats(&[((100, 50), &[1.0, 2.0]), ((200, 50), &[3.0]), ((300, 50), &[4.0])]);
+        let options = OutputOptions {
+            include_raw: true,
+            ..options(Format::Html)
+        };
+        let html = output(|out| write_stats(out, &stats, 4, &options));
+        assert!(html.starts_with("<!DOCTYPE html>\n<html>"));
+        assert!(html.trim_end().ends_with("</html>"));
+        for tag in ["html", "head", "body", "table", "thead", "tbody", "tr", "th", "td", "svg", "script", "style"] {
+            let opened = html.matches(&format!("<{tag}>")).count() + html.matches(&format!("<{tag} ")).count();
+            assert_eq!(opened, html.matches(&format!("</{tag}>")).count(), "<{tag}>");
+        }
+        let body = &html[html.find("<tbody>").unwrap()..html.find("</tbody>").unwrap()];
+        assert_eq!(body.matches("<tr>").count(), stats.len());
+        assert_eq!(html.matches("<th>").count() + html.matches("<th ").count(), COLUMNS.len() + 1);
+        assert!(body.contains("<td>100</td><td>50</td><td>2</td>"));
+    }
+
+    #[test]
+    fn compact_numbers_have_si_suffixes() {
+        for (value, expected) in [
+            (1200.0, "1.2k"),
+            (0.0000567, "56.7µ"),
+            (3_400_000.0, "3.4M"),
+            (-1200.0, "-1.2k"),
+            (12.5, "12.5"),
+            (999.96, "1k"),
+            (0.0, "0"),
+            (f32::NAN, "NaN"),
+            (f32::INFINITY, "inf"),
+            (1e20, "100000000000000000000"),
+        ] {
+            assert_eq!(format_compact(value), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn only_human_readable_formats_are_compact() {
+        let stats = stats(&[((100, 50), &[1000.0, 1400.0])]);
+        let compact = |format| OutputOptions { compact: true, ..options(format) };
+
+        let grid = output(|out| write_stats(out, &stats, 2, &compact(Format::Grid)));
+        assert!(grid.lines().any(|line| line.trim_start().starts_with("100") && line.ends_with("1.2k")), "{grid}");
+
+        let csv = output(|out| write_stats(out, &stats, 2, &compact(Format::Csv)));
+        assert!(csv.contains(",1200,"), "{csv}");
+        assert!(!csv.contains("1.2k"), "{csv}");
+    }
+}
@@ -0,0 +1,596 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+};
+
+use nom::{
+    bytes::complete::{tag, take_till1, take_while1, take_while_m_n},
+    character::complete::{char, digit0, digit1, one_of, satisfy, u32, space0, space1},
+    combinator::{consumed, map, map_res, not, opt, recognize},
+    multi::{many0, many1},
+    sequence::{pair, preceded, separated_pair, terminated, tuple},
+    IResult, branch::alt, number::complete::float,
+};
+use rayon::prelude::*;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+// fn parse_results(input: &str) -> IResult<&str, Summary> {
+//     let (input, _) = tag("#")(input)?;
+//     let (input, (red, green, blue)) = (hex_primary, hex_primary, hex_primary).parse(input)?;
+//     Ok((input, Color { red, green, blue }))
+//   }
+
+#[derive(Debug, PartialEq)]
+pub enum Entry {
+    /// The run succeeded, with the details some logs add after `SUCCESS`.
+    Success(Option<SuccessDetails>),
+    /// A run time in seconds, along with the unit it was written in, if it
+    /// had a suffix.
+    RunTime(f32, Option<Unit>),
+}
+
+/// The two numbers in a `SUCCESS 27 0.0` entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SuccessDetails {
+    /// The first number, the generation the run succeeded in.
+    pub generation: u32,
+    /// The run's final fitness (error).
+    pub fitness: f32,
+}
+
+/// A unit suffix on a run time, like the `ms` in `250ms`. Run times are kept
+/// in seconds, so suffixed values are converted as they're parsed and values
+/// without a suffix are taken to already be in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Milliseconds,
+    Seconds,
+    Minutes,
+    Hours,
+}
+
+impl Unit {
+    pub fn to_seconds(self, value: f32) -> f32 {
+        match self {
+            Self::Milliseconds => value / 1000.0,
+            Self::Seconds => value,
+            Self::Minutes => value * 60.0,
+            Self::Hours => value * 3600.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Line<'a> {
+    /// The path this line came from, exactly as written (e.g.
+    /// `PS_100/NG_50/run_3.output`).
+    pub path: &'a str,
+    pub population_size: u32,
+    pub num_generations: u32,
+    pub run_number: u32,
+    pub entry: Entry,
+    /// The keys of a labeled (`time=12.5 evals=3000`) entry, in the order
+    /// they were written; empty for any other entry.
+    pub labels: Vec<&'a str>,
+}
+
+/// Writes the line back out in the canonical
+/// `PS_<pop>/NG_<gens>/run_<n>.output:<entry>` form, which `parse_line` parses.
+/// Run times that had a unit are written in seconds with an `s` suffix.
+impl fmt::Display for Line<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PS_{}/NG_{}/run_{}.output:", self.population_size, self.num_generations, self.run_number)?;
+        match self.entry {
+            Entry::Success(None) => write!(f, "SUCCESS"),
+            Entry::Success(Some(SuccessDetails { generation, fitness })) => write!(f, "SUCCESS {generation} {fitness}"),
+            Entry::RunTime(value, None) => write!(f, " {value}"),
+            Entry::RunTime(value, Some(_)) => write!(f, " {value}s"),
+        }
+    }
+}
+
+/// How the population size and generations are written in a line's path.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum KeyStyle {
+    /// `PS_<pop>/NG_<gens>`
+    #[default]
+    Prefix,
+    /// `pop=<pop>&gen=<gens>`
+    Query,
+}
+
+fn pop_size(s: &str) -> IResult<&str, u32> {
+    preceded(tag("PS_"), u32)(s)
+}
+
+fn num_gens(s: &str) -> IResult<&str, u32> {
+    preceded(tag("/NG_"), u32)(s)
+}
+
+/// The `(pop, gens)` key at the start of a path, written in `key_style`.
+fn config_key(s: &str, key_style: KeyStyle) -> IResult<&str, (u32, u32)> {
+    match key_style {
+        KeyStyle::Prefix => pair(pop_size, num_gens)(s),
+        KeyStyle::Query => separated_pair(preceded(tag("pop="), u32), char('&'), preceded(tag("gen="), u32))(s),
+    }
+}
+
+fn run_num(s: &str) -> IResult<&str, u32> {
+    // run_29.output
+    map(
+        tuple((tag("/run_"), u32, tag(".output"))),
+        |(_, run_num, _)| run_num,
+    )(s)
+}
+
+/// Any extra `/segment`s between the generations and the run, such as the
+/// `/trial_2` in `PS_100/NG_50/trial_2/run_3.output`.
+fn intermediate_segments(s: &str) -> IResult<&str, ()> {
+    map(
+        many0(preceded(
+            not(tag("/run_")),
+            preceded(char('/'), take_till1(|c| c == '/' || c == ':')),
+        )),
+        |_| (),
+    )(s)
+}
+
+fn path<'a>(s: &'a str, options: &ParseOptions) -> IResult<&'a str, (u32, u32, u32)> {
+    let key = |s| config_key(s, options.key_style);
+    if options.relaxed_paths {
+        map(
+            tuple((key, intermediate_segments, run_num)),
+            |((pop_size, num_gens), _, run_num)| (pop_size, num_gens, run_num),
+        )(s)
+    } else {
+        map(pair(key, run_num), |((pop_size, num_gens), run_num)| (pop_size, num_gens, run_num))(s)
+    }
+}
+
+/// `SUCCESS`, optionally followed by a generation and final fitness.
+fn success(s: &str) -> IResult<&str, Entry> {
+    let details = map(separated_pair(u32, space1, float), |(generation, fitness)| SuccessDetails { generation, fitness });
+    map(preceded(tag("SUCCESS"), opt(preceded(space1, details))), Entry::Success)(s)
+}
+
+/// Options that change what the line parser accepts.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParseOptions {
+    /// Accept run times with comma digit grouping, like `1,234.5`.
+    pub allow_thousands_sep: bool,
+    /// Skip (and warn about) lines that don't parse instead of failing.
+    pub lenient: bool,
+    /// Allow extra path segments between `NG_<gens>` and `run_<n>.output`,
+    /// so that e.g. every trial of a configuration is merged together.
+    pub relaxed_paths: bool,
+    /// Drop a final line that isn't newline-terminated, since it may still
+    /// be in the middle of being written.
+    pub skip_incomplete_last_line: bool,
+    /// How paths write the population size and generations.
+    pub key_style: KeyStyle,
+}
+
+/// A float whose integer part is grouped into threes by commas, e.g.
+/// `1,234.5` or `12,345,678`. At least one comma is required, so plain
+/// floats are left to `float`.
+fn grouped_float(s: &str) -> IResult<&str, f32> {
+    map_res(
+        recognize(tuple((
+            opt(char('-')),
+            digit1,
+            many1(preceded(char(','), take_while_m_n(3, 3, |c: char| c.is_ascii_digit()))),
+            opt(pair(char('.'), digit0)),
+            opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+        ))),
+        |grouped: &str| grouped.replace(',', "").parse::<f32>(),
+    )(s)
+}
+
+/// `ms`, `s`, `min` (or `m`), or `h`, optionally after some spaces. The
+/// unit has to end a word, so the `s` of a trailing `steps=3` isn't one.
+fn unit(s: &str) -> IResult<&str, Unit> {
+    preceded(
+        space0,
+        terminated(
+            alt((
+                map(tag("ms"), |_| Unit::Milliseconds),
+                map(tag("min"), |_| Unit::Minutes),
+                map(tag("s"), |_| Unit::Seconds),
+                map(tag("m"), |_| Unit::Minutes),
+                map(tag("h"), |_| Unit::Hours),
+            )),
+            not(satisfy(|c| c.is_alphanumeric() || c == '_')),
+        ),
+    )(s)
+}
+
+fn run_time<'a>(s: &'a str, options: &ParseOptions) -> IResult<&'a str, Entry> {
+    let number = |s| {
+        if options.allow_thousands_sep {
+            alt((grouped_float, float))(s)
+        } else {
+            float(s)
+        }
+    };
+    map(pair(preceded(space0, number), opt(unit)), |(value, unit)| {
+        Entry::RunTime(unit.map_or(value, |unit| unit.to_seconds(value)), unit)
+    })(s)
+}
+
+fn entry<'a>(s: &'a str, options: &ParseOptions) -> IResult<&'a str, Entry> {
+    alt((success, |s| run_time(s, options)))(s)
+}
+
+/// One `key=value` field of a labeled entry.
+fn label(s: &str) -> IResult<&str, (&str, &str)> {
+    preceded(
+        space0,
+        separated_pair(
+            take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+            char('='),
+            take_till1(char::is_whitespace),
+        ),
+    )(s)
+}
+
+/// A labeled entry like `time=12.5 evals=3000`: whitespace separated
+/// `key=value` fields, where `time` is the run time (with an optional unit)
+/// and any other fields are recorded only by key.
+fn labeled_entry<'a>(s: &'a str, options: &ParseOptions) -> Result<(Entry, Vec<&'a str>), ParseError> {
+    let (_, fields) = many1(label)(s).map_err(|_| ParseError::UnknownEntry(s.to_owned()))?;
+    let (_, time) = fields
+        .iter()
+        .find(|(key, _)| *key == "time")
+        .ok_or_else(|| ParseError::MissingTime(s.to_owned()))?;
+    let (_, entry) = run_time(time, options).map_err(|_| ParseError::UnknownEntry(s.to_owned()))?;
+    Ok((entry, fields.into_iter().map(|(key, _)| key).collect()))
+}
+
+/// Why a line couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ParseError {
+    /// The line doesn't start with a `PS_<pop>/NG_<gens>/run_<n>.output` path.
+    #[error("malformed path at the start of {0:?}")]
+    MalformedPath(String),
+    /// The path isn't followed by a `:`.
+    #[error("expected `:` after the path, found {0:?}")]
+    MissingColon(String),
+    /// What follows the `:` is neither `SUCCESS`, a run time, nor
+    /// `key=value` fields.
+    #[error("unknown entry {0:?}, expected `SUCCESS`, a run time, or `key=value` fields")]
+    UnknownEntry(String),
+    /// A labeled entry without a `time` field.
+    #[error("labeled entry {0:?} has no `time=` field")]
+    MissingTime(String),
+    #[error("negative run time {0}")]
+    NegativeRunTime(f32),
+    /// A run time of NaN or infinity.
+    #[error("run time {0} isn't a finite number")]
+    NonFiniteRunTime(f32),
+}
+
+pub fn parse_line<'a>(s: &'a str, options: &ParseOptions) -> Result<Line<'a>, ParseError> {
+    let (rest, (path, (population_size, num_generations, run_number))) =
+        consumed(|s| path(s, options))(s).map_err(|_| ParseError::MalformedPath(s.to_owned()))?;
+    let rest = rest
+        .strip_prefix(':')
+        .ok_or_else(|| ParseError::MissingColon(rest.to_owned()))?;
+    let (entry, labels) = match entry(rest, options) {
+        Ok((_, entry)) => (entry, Vec::new()),
+        Err(_) => labeled_entry(rest, options)?,
+    };
+    match entry {
+        Entry::RunTime(value, _) if !value.is_finite() => return Err(ParseError::NonFiniteRunTime(value)),
+        Entry::RunTime(value, _) if value < 0.0 => return Err(ParseError::NegativeRunTime(value)),
+        _ => {}
+    }
+    Ok(Line {
+        path,
+        population_size,
+        num_generations,
+        run_number,
+        entry,
+        labels,
+    })
+}
+
+/// Checks that every labeled line of a configuration has the same set of
+/// keys as the first labeled line of that configuration, for
+/// `--strict-schema`. Unlabeled lines (like `SUCCESS`) aren't checked.
+pub fn check_schema(lines: &[Line]) -> anyhow::Result<()> {
+    let mut schemas = HashMap::<(u32, u32), (BTreeSet<&str>, &Line)>::new();
+    for line in lines.iter().filter(|line| !line.labels.is_empty()) {
+        let keys = line.labels.iter().copied().collect::<BTreeSet<_>>();
+        let key = (line.population_size, line.num_generations);
+        let (expected, first) = schemas.entry(key).or_insert_with(|| (keys.clone(), line));
+        anyhow::ensure!(
+            *expected == keys,
+            "{} has keys {:?}, but {} (the first labeled line of configuration {}:{}) has {:?}",
+            line.path,
+            keys,
+            first.path,
+            key.0,
+            key.1,
+            expected,
+        );
+    }
+    Ok(())
+}
+
+/// Remembers which runs have logged a `SUCCESS`, so one that logs it again
+/// is caught instead of counted twice. Runs are told apart by their path as
+/// written, so merged `--relaxed-paths` trials don't collide.
+#[derive(Debug, Default)]
+pub struct OutcomeCheck {
+    succeeded: HashSet<String>,
+}
+
+impl OutcomeCheck {
+    /// Whether `line` should be counted. A repeated `SUCCESS` isn't: it's
+    /// an error, or with `lenient` a warning.
+    pub fn admits(&mut self, line: &Line, lenient: bool) -> anyhow::Result<bool> {
+        if !matches!(line.entry, Entry::Success(_)) || self.succeeded.insert(line.path.to_owned()) {
+            return Ok(true);
+        }
+        anyhow::ensure!(lenient, "{} logged SUCCESS more than once (see --lenient)", line.path);
+        warn!("ignoring repeated SUCCESS for {}", line.path);
+        Ok(false)
+    }
+}
+
+/// Parses every line of `text`, returning the parsed lines and how many were
+/// skipped. Without `options.lenient` the first bad line is an error.
+pub fn parse_lines<'a>(text: &'a str, options: &ParseOptions) -> anyhow::Result<(Vec<Line<'a>>, usize)> {
+    let text = if options.skip_incomplete_last_line && !text.is_empty() && !text.ends_with('\n') {
+        let complete = text.rfind('\n').map_or("", |end| &text[..=end]);
+        warn!("dropping incomplete last line {:?}", &text[complete.len()..]);
+        complete
+    } else {
+        text
+    };
+    // Lines are parsed in parallel, but the results are gathered in order so
+    // the first bad line is the one reported.
+    let numbered = text.lines().enumerate().collect::<Vec<_>>();
+    let parsed = numbered
+        .into_par_iter()
+        .map(|(index, s)| parse_numbered_line(s, index + 1, options))
+        .collect::<Vec<_>>();
+    let mut lines = Vec::new();
+    let mut skipped = 0;
+    for line in parsed {
+        match line? {
+            Some(line) => lines.push(line),
+            None => skipped += 1,
+        }
+    }
+    info!(parsed = lines.len(), skipped, "finished parsing");
+    Ok((lines, skipped))
+}
+
+/// Parses line number `line_number`. A bad line is an error, unless
+/// `options.lenient` is set, in which case it is logged and `None` returned.
+pub fn parse_numbered_line<'a>(s: &'a str, line_number: usize, options: &ParseOptions) -> anyhow::Result<Option<Line<'a>>> {
+    match parse_line(s, options) {
+        Ok(line) => {
+            debug!(line_number, ?line, "parsed line");
+            Ok(Some(line))
+        }
+        Err(error) if options.lenient => {
+            warn!(line_number, %error, "skipping unparseable line {s:?}");
+            Ok(None)
+        }
+        Err(error) => Err(anyhow::Error::new(error).context(format!("Couldn't parse line {line_number}: {s:?}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_time_of(s: &str, options: &ParseOptions) -> f32 {
+        match parse_line(s, options).unwrap().entry {
+            Entry::RunTime(value, _) => value,
+            entry => panic!("expected a run time, got {entry:?}"),
+        }
+    }
+
+    #[test]
+    fn thousands_separators_are_only_accepted_with_the_flag() {
+        let grouped = ParseOptions {
+            allow_thousands_sep: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(run_time_of("PS_100/NG_50/run_1.output: 1,234.5", &grouped), 1234.5);
+        assert_eq!(run_time_of("PS_100/NG_50/run_1.output: 12,345,678", &grouped), 12_345_678.0);
+        assert_eq!(run_time_of("PS_100/NG_50/run_1.output: 1234.5", &grouped), 1234.5);
+
+        // Without the flag the float parser stops at the comma, as it always has.
+        assert_eq!(run_time_of("PS_100/NG_50/run_1.output: 1,234.5", &ParseOptions::default()), 1.0);
+    }
+
+    #[test]
+    fn a_unit_has_to_end_a_word() {
+        let entry = |s| parse_line(s, &ParseOptions::default()).unwrap().entry;
+        assert_eq!(entry("PS_100/NG_50/run_1.output: 250ms"), Entry::RunTime(0.25, Some(Unit::Milliseconds)));
+        assert_eq!(entry("PS_100/NG_50/run_1.output: 2 min"), Entry::RunTime(120.0, Some(Unit::Minutes)));
+        assert_eq!(entry("PS_100/NG_50/run_1.output: 1.5 steps=3"), Entry::RunTime(1.5, None));
+        assert_eq!(entry("PS_100/NG_50/run_1.output: 1.5ms_total"), Entry::RunTime(1.5, None));
+    }
+
+    /// Records the level of every event logged while it's the subscriber.
+    #[derive(Clone, Default)]
+    struct Levels(std::sync::Arc<std::sync::Mutex<Vec<tracing::Level>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Levels {
+        fn on_event(&self, event: &tracing::Event<'_>, _: tracing_subscriber::layer::Context<'_, S>) {
+            self.0.lock().unwrap().push(*event.metadata().level());
+        }
+    }
+
+    #[test]
+    fn a_skipped_line_logs_a_warning() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let levels = Levels::default();
+        let subscriber = tracing_subscriber::registry().with(levels.clone());
+        let lenient = ParseOptions {
+            lenient: true,
+            ..ParseOptions::default()
+        };
+        let line = tracing::subscriber::with_default(subscriber, || parse_numbered_line("not a run", 3, &lenient).unwrap());
+        assert!(line.is_none());
+        assert_eq!(*levels.0.lock().unwrap(), [tracing::Level::WARN]);
+    }
+
+    #[test]
+    fn relaxed_paths_skip_intermediate_segments() {
+        let relaxed = ParseOptions {
+            relaxed_paths: true,
+            ..ParseOptions::default()
+        };
+        for s in ["PS_100/NG_50/run_3.output: 1.5", "PS_100/NG_50/trial_2/run_3.output: 1.5", "PS_100/NG_50/a/b/run_3.output: 1.5"] {
+            let line = parse_line(s, &relaxed).unwrap();
+            assert_eq!((line.population_size, line.num_generations, line.run_number), (100, 50, 3));
+        }
+        assert_eq!(parse_line("PS_100/NG_50/trial_2/run_3.output: 1.5", &relaxed).unwrap().path, "PS_100/NG_50/trial_2/run_3.output");
+
+        assert!(parse_line("PS_100/NG_50/run_3.output: 1.5", &ParseOptions::default()).is_ok());
+        assert!(matches!(
+            parse_line("PS_100/NG_50/trial_2/run_3.output: 1.5", &ParseOptions::default()),
+            Err(ParseError::MalformedPath(_))
+        ));
+    }
+
+    #[test]
+    fn an_unterminated_last_line_is_only_dropped_with_the_flag() {
+        let text = "PS_100/NG_50/run_1.output: 4.5\nPS_100/NG_50/run_2.output: 1";
+        let skipping = ParseOptions {
+            skip_incomplete_last_line: true,
+            ..ParseOptions::default()
+        };
+        let (lines, skipped) = parse_lines(text, &skipping).unwrap();
+        assert_eq!((lines.len(), skipped), (1, 0));
+        assert_eq!(lines[0].run_number, 1);
+
+        let (lines, _) = parse_lines(text, &ParseOptions::default()).unwrap();
+        assert_eq!(lines.len(), 2);
+
+        // A terminated last line is kept either way.
+        let (lines, _) = parse_lines("PS_100/NG_50/run_1.output: 4.5\n", &skipping).unwrap();
+        assert_eq!(lines.len(), 1);
+        let (lines, _) = parse_lines("PS_100/NG_50/run_1.output: 4.5", &skipping).unwrap();
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn each_kind_of_bad_line_has_its_own_error() {
+        let options = ParseOptions::default();
+        let error = |s| parse_line(s, &options).unwrap_err();
+        assert!(matches!(error("PS_100/run_1.output: 1.0"), ParseError::MalformedPath(_)));
+        assert!(matches!(error("PS_100/NG_50/run_1.output 1.0"), ParseError::MissingColon(_)));
+        assert!(matches!(error("PS_100/NG_50/run_1.output:FAILURE"), ParseError::UnknownEntry(_)));
+        assert!(matches!(error("PS_100/NG_50/run_1.output: evals=3000"), ParseError::MissingTime(_)));
+        assert_eq!(error("PS_100/NG_50/run_1.output: -2.5"), ParseError::NegativeRunTime(-2.5));
+        assert!(matches!(error("PS_100/NG_50/run_1.output: inf"), ParseError::NonFiniteRunTime(_)));
+        assert!(matches!(error("PS_100/NG_50/run_1.output: NaN"), ParseError::NonFiniteRunTime(_)));
+    }
+
+    #[test]
+    fn strict_schema_rejects_an_extra_key() {
+        let consistent = "\
+PS_100/NG_50/run_1.output: time=1.5 evals=3000
+PS_100/NG_50/run_1.output:SUCCESS
+PS_100/NG_50/run_2.output: evals=2000 time=2.5
+PS_200/NG_50/run_1.output: time=1.0
+";
+        let (lines, _) = parse_lines(consistent, &ParseOptions::default()).unwrap();
+        check_schema(&lines).unwrap();
+
+        let extra = "\
+PS_100/NG_50/run_1.output: time=1.5 evals=3000
+PS_100/NG_50/run_2.output: time=2.5 evals=2000 depth=7
+";
+        let (lines, _) = parse_lines(extra, &ParseOptions::default()).unwrap();
+        let error = check_schema(&lines).unwrap_err().to_string();
+        assert!(error.contains("PS_100/NG_50/run_2.output"), "{error}");
+        assert!(error.contains("depth"), "{error}");
+    }
+
+    #[test]
+    fn success_can_carry_a_generation_and_fitness() {
+        let options = ParseOptions::default();
+        let entry = |s| parse_line(s, &options).unwrap().entry;
+        assert!(matches!(
+            entry("PS_100/NG_50/run_1.output:SUCCESS 27 0.0"),
+            Entry::Success(Some(SuccessDetails { generation: 27, fitness })) if fitness == 0.0
+        ));
+        assert!(matches!(
+            entry("PS_100/NG_50/run_1.output:SUCCESS 3 1.5e-3"),
+            Entry::Success(Some(SuccessDetails { generation: 3, fitness })) if fitness == 1.5e-3
+        ));
+        assert!(matches!(entry("PS_100/NG_50/run_1.output:SUCCESS"), Entry::Success(None)));
+
+        let line = parse_line("PS_100/NG_50/run_1.output:SUCCESS 27 0.5", &options).unwrap();
+        assert_eq!(line.to_string(), "PS_100/NG_50/run_1.output:SUCCESS 27 0.5");
+    }
+
+    #[test]
+    fn one_thread_parses_the_same_as_many() {
+        let text = (1..=200)
+            .map(|i| format!("PS_{}/NG_50/run_{i}.output: {}\n", i % 7 * 100, i as f32 / 4.0))
+            .collect::<String>();
+        let parse = || {
+            let (lines, _) = parse_lines(&text, &ParseOptions::default()).unwrap();
+            lines.iter().map(ToString::to_string).collect::<Vec<_>>()
+        };
+        let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap().install(parse);
+        let multi = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap().install(parse);
+        assert_eq!(single.len(), 200);
+        assert_eq!(single, multi);
+    }
+
+    #[test]
+    fn both_key_styles_give_the_same_key() {
+        let query = ParseOptions {
+            key_style: KeyStyle::Query,
+            ..ParseOptions::default()
+        };
+        let prefixed = parse_line("PS_100/NG_50/run_3.output: 1.5", &ParseOptions::default()).unwrap();
+        let queried = parse_line("pop=100&gen=50/run_3.output: 1.5", &query).unwrap();
+        let key = |line: &Line| (line.population_size, line.num_generations, line.run_number);
+        assert_eq!(key(&prefixed), (100, 50, 3));
+        assert_eq!(key(&queried), key(&prefixed));
+        // Dumping always writes the prefix style.
+        assert_eq!(queried.to_string(), prefixed.to_string());
+
+        assert!(parse_line("pop=100&gen=50/run_3.output: 1.5", &ParseOptions::default()).is_err());
+        assert!(parse_line("PS_100/NG_50/run_3.output: 1.5", &query).is_err());
+    }
+
+    #[test]
+    fn a_repeated_success_is_counted_once() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let text = "\
+PS_100/NG_50/run_1.output: 4.5
+PS_100/NG_50/run_1.output:SUCCESS
+PS_100/NG_50/run_1.output:SUCCESS
+PS_100/NG_50/run_2.output:SUCCESS
+";
+        let (lines, _) = parse_lines(text, &ParseOptions::default()).unwrap();
+
+        let levels = Levels::default();
+        let subscriber = tracing_subscriber::registry().with(levels.clone());
+        let admitted = tracing::subscriber::with_default(subscriber, || {
+            let mut outcomes = OutcomeCheck::default();
+            lines.iter().map(|line| outcomes.admits(line, true).unwrap()).collect::<Vec<_>>()
+        });
+        assert_eq!(admitted, [true, true, false, true]);
+        assert_eq!(*levels.0.lock().unwrap(), [tracing::Level::WARN]);
+
+        let mut outcomes = OutcomeCheck::default();
+        let error = lines.iter().map(|line| outcomes.admits(line, false)).find_map(|admits| admits.err()).unwrap();
+        assert!(error.to_string().contains("PS_100/NG_50/run_1.output logged SUCCESS more than once"), "{error}");
+    }
+}
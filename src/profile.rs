@@ -0,0 +1,66 @@
+//! Wall-clock timing of the major phases of a run, for `--profile`. Timing is
+//! off (and `time` just calls through) until `enable` is called.
+
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// The phases that are timed, in the order they run and are reported.
+pub const PHASES: [&str; 6] = ["read", "parse", "aggregate", "stats", "sort", "output"];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TIMES: Mutex<[Duration; PHASES.len()]> = Mutex::new([Duration::ZERO; PHASES.len()]);
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Runs `f`, adding the time it takes to `phase` (one of `PHASES`) when
+/// timing is enabled. A phase that runs several times, like sorting once
+/// per table, accumulates.
+pub fn time<T>(phase: &str, f: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let index = PHASES.iter().position(|&p| p == phase).expect("phases are from PHASES");
+    TIMES.lock().unwrap_or_else(|e| e.into_inner())[index] += elapsed;
+    result
+}
+
+/// Writes every phase's time in seconds, including those that didn't run.
+/// `output` includes the time spent sorting along the way.
+pub fn write_report(out: &mut impl Write) -> io::Result<()> {
+    let times = *TIMES.lock().unwrap_or_else(|e| e.into_inner());
+    writeln!(out, "profile:")?;
+    for (phase, time) in PHASES.iter().zip(times) {
+        writeln!(out, "  {phase:<9} {:.6}s", time.as_secs_f64())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_report_lists_every_phase() {
+        enable();
+        assert_eq!(time("parse", || 42), 42);
+        let mut out = Vec::new();
+        write_report(&mut out).unwrap();
+        let report = String::from_utf8(out).unwrap();
+        let mut lines = report.lines();
+        assert_eq!(lines.next(), Some("profile:"));
+        let phases = lines.map(|line| line.split_whitespace().next().unwrap()).collect::<Vec<_>>();
+        assert_eq!(phases, PHASES);
+        assert!(report.lines().skip(1).all(|line| line.ends_with('s')));
+    }
+}
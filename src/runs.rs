@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use crate::{mean, Entry, Line};
+
+/// Everything logged for a single run. Unlike `Result`, this keeps each run's
+/// time together with whether that same run succeeded.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Run {
+    pub run_time: Option<f32>,
+    pub success: bool,
+}
+
+/// A configuration's runs, keyed (and so ordered) by run number and then
+/// path. The path tells apart `--relaxed-paths` trials that reuse a run
+/// number.
+pub type Runs<'a> = BTreeMap<(u32, &'a str), Run>;
+
+pub fn runs_by_config<'a>(lines: &[Line<'a>]) -> BTreeMap<(u32, u32), Runs<'a>> {
+    let mut runs = BTreeMap::<_, Runs>::new();
+    for line in lines {
+        let key = (line.population_size, line.num_generations);
+        let run = runs.entry(key).or_default().entry((line.run_number, line.path)).or_default();
+        match line.entry {
+            Entry::Success(_) => run.success = true,
+            Entry::RunTime(value, _) => run.run_time = Some(value),
+        }
+    }
+    runs
+}
+
+/// The fastest run time among the runs that succeeded, or `None` if no run
+/// with a time succeeded. Failed runs are ignored no matter how fast.
+pub fn first_success_time(runs: &Runs) -> Option<f32> {
+    success_times(runs).first().copied()
+}
+
+/// The fractions of successful runs reported by `success_cdf`.
+pub const CDF_FRACTIONS: [f32; 4] = [0.25, 0.5, 0.75, 0.9];
+
+/// Sorted run times of the runs that succeeded.
+fn success_times(runs: &Runs) -> Vec<f32> {
+    let mut times = runs
+        .values()
+        .filter(|r| r.success)
+        .filter_map(|r| r.run_time)
+        .collect::<Vec<_>>();
+    times.sort_unstable_by(f32::total_cmp);
+    times
+}
+
+/// For each of `fractions`, the earliest run time by which at least that
+/// fraction of the successful runs had finished, or `None` if nothing
+/// succeeded. Only successful runs count, so this is the distribution of time
+/// to solution rather than of run time in general.
+pub fn success_cdf(runs: &Runs, fractions: &[f32]) -> Vec<(f32, Option<f32>)> {
+    let times = success_times(runs);
+    fractions
+        .iter()
+        .map(|&fraction| {
+            let needed = (fraction * times.len() as f32).ceil() as usize;
+            (fraction, times.get(needed.max(1) - 1).copied())
+        })
+        .collect()
+}
+
+/// One point on a learning curve: the estimates using only the first `n` runs.
+#[derive(Debug, Clone, Copy)]
+pub struct CurvePoint {
+    pub n: usize,
+    pub mean_estimate: f32,
+    pub success_rate_estimate: f32,
+}
+
+/// The running mean run time and success rate after the first 1, 2, 4, 8, ...
+/// runs (in run number order), finishing with all of the runs. Runs without a
+/// time still count towards the success rate.
+pub fn learning_curve(runs: &Runs) -> Vec<CurvePoint> {
+    let runs = runs.values().collect::<Vec<_>>();
+    let mut sizes = powers_of_two(runs.len());
+    if sizes.last() != Some(&runs.len()) && !runs.is_empty() {
+        sizes.push(runs.len());
+    }
+    sizes
+        .into_iter()
+        .map(|n| {
+            let first = &runs[..n];
+            let run_times = first.iter().filter_map(|r| r.run_time).collect::<Vec<_>>();
+            let successes = first.iter().filter(|r| r.success).count();
+            CurvePoint {
+                n,
+                mean_estimate: mean(&run_times),
+                success_rate_estimate: successes as f32 / n as f32,
+            }
+        })
+        .collect()
+}
+
+/// 1, 2, 4, ... up to and including `limit`.
+fn powers_of_two(limit: usize) -> Vec<usize> {
+    std::iter::successors(Some(1usize), |n| n.checked_mul(2))
+        .take_while(|&n| n <= limit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_lines, ParseOptions};
+
+    fn runs(text: &str) -> BTreeMap<(u32, u32), Runs<'_>> {
+        let (lines, _) = parse_lines(text, &ParseOptions::default()).unwrap();
+        runs_by_config(&lines)
+    }
+
+    #[test]
+    fn the_learning_curve_doubles_then_ends_with_every_run() {
+        let text = "\
+PS_100/NG_50/run_1.output: 1.0
+PS_100/NG_50/run_1.output:SUCCESS
+PS_100/NG_50/run_2.output: 2.0
+PS_100/NG_50/run_3.output: 3.0
+PS_100/NG_50/run_3.output:SUCCESS
+PS_100/NG_50/run_4.output: 4.0
+PS_100/NG_50/run_5.output: 5.0
+";
+        let curve = learning_curve(&runs(text)[&(100, 50)]);
+        let points = curve.iter().map(|p| (p.n, p.mean_estimate, p.success_rate_estimate)).collect::<Vec<_>>();
+        assert_eq!(points, [(1, 1.0, 1.0), (2, 1.5, 0.5), (4, 2.5, 0.5), (5, 3.0, 0.4)]);
+
+        assert!(learning_curve(&Runs::new()).is_empty());
+    }
+
+    #[test]
+    fn first_success_time_ignores_faster_failures() {
+        let text = "\
+PS_100/NG_50/run_1.output: 9.0
+PS_100/NG_50/run_1.output:SUCCESS
+PS_100/NG_50/run_2.output: 2.0
+PS_100/NG_50/run_3.output: 12.0
+PS_100/NG_50/run_3.output:SUCCESS
+PS_200/NG_50/run_1.output: 1.0
+";
+        let runs = runs(text);
+        assert_eq!(first_success_time(&runs[&(100, 50)]), Some(9.0));
+        assert_eq!(first_success_time(&runs[&(200, 50)]), None);
+    }
+
+    #[test]
+    fn the_success_cdf_only_counts_successful_runs() {
+        let text = "\
+PS_100/NG_50/run_1.output: 4.0
+PS_100/NG_50/run_1.output:SUCCESS
+PS_100/NG_50/run_2.output: 1.0
+PS_100/NG_50/run_2.output:SUCCESS
+PS_100/NG_50/run_3.output: 0.5
+PS_100/NG_50/run_4.output: 3.0
+PS_100/NG_50/run_4.output:SUCCESS
+PS_100/NG_50/run_5.output: 2.0
+PS_100/NG_50/run_5.output:SUCCESS
+PS_200/NG_50/run_1.output: 1.0
+";
+        let runs = runs(text);
+        assert_eq!(
+            success_cdf(&runs[&(100, 50)], &CDF_FRACTIONS),
+            [(0.25, Some(1.0)), (0.5, Some(2.0)), (0.75, Some(3.0)), (0.9, Some(4.0))]
+        );
+        assert_eq!(success_cdf(&runs[&(200, 50)], &[0.5]), [(0.5, None)]);
+    }
+
+    #[test]
+    fn relaxed_trials_that_reuse_a_run_number_stay_separate() {
+        let text = "\
+PS_100/NG_50/trial_1/run_1.output: 5.0
+PS_100/NG_50/trial_1/run_1.output:SUCCESS
+PS_100/NG_50/trial_2/run_1.output: 1.0
+";
+        let relaxed = ParseOptions {
+            relaxed_paths: true,
+            ..ParseOptions::default()
+        };
+        let (lines, _) = parse_lines(text, &relaxed).unwrap();
+        let runs = runs_by_config(&lines);
+        assert_eq!(runs[&(100, 50)].len(), 2);
+        assert_eq!(first_success_time(&runs[&(100, 50)]), Some(5.0));
+    }
+}
@@ -0,0 +1,104 @@
+use std::cmp::Ordering;
+
+use clap::ValueEnum;
+use statrs::distribution::{ContinuousCDF, Normal, StudentsT};
+
+use crate::{mean, stats::z_for_confidence, variance};
+
+/// The power `required_sample_size` plans for.
+pub const DEFAULT_POWER: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Test {
+    /// Welch's t-test on the mean run times, not assuming equal variances
+    Welch,
+}
+
+/// Welch's unequal-variances t-test comparing the means of `a` and `b`.
+/// Returns the t statistic, the Welch–Satterthwaite degrees of freedom, and
+/// the two-sided p-value. Both samples need at least two values, otherwise
+/// everything is NaN.
+pub fn welch_t_test(a: &[f32], b: &[f32]) -> (f64, f64, f64) {
+    if a.len() < 2 || b.len() < 2 {
+        return (f64::NAN, f64::NAN, f64::NAN);
+    }
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let se_a = f64::from(variance(a)) / n_a;
+    let se_b = f64::from(variance(b)) / n_b;
+    let t = (f64::from(mean(a)) - f64::from(mean(b))) / (se_a + se_b).sqrt();
+    let df = (se_a + se_b).powi(2) / (se_a.powi(2) / (n_a - 1.0) + se_b.powi(2) / (n_b - 1.0));
+    let p = match StudentsT::new(0.0, 1.0, df) {
+        Ok(dist) => 2.0 * dist.sf(t.abs()),
+        Err(_) => f64::NAN,
+    };
+    (t, df, p)
+}
+
+/// The runs per configuration needed for a two-sided test at `confidence` to
+/// detect a difference of `effect` between the mean run times of `a` and `b`
+/// with probability `power`, using the normal approximation
+/// `(z_confidence + z_power)^2 * (var_a + var_b) / effect^2` with the
+/// observed variances. NaN if either sample has fewer than two values.
+pub fn required_sample_size(a: &[f32], b: &[f32], effect: f64, confidence: f64, power: f64) -> f64 {
+    if a.len() < 2 || b.len() < 2 {
+        return f64::NAN;
+    }
+    let normal = Normal::new(0.0, 1.0).expect("the standard normal is valid");
+    let z = z_for_confidence(confidence) + normal.inverse_cdf(power);
+    let variances = f64::from(variance(a)) + f64::from(variance(b));
+    (z.powi(2) * variances / effect.powi(2)).ceil()
+}
+
+/// The probability that a run of `a` is faster than a run of `b`: the
+/// fraction of all (a, b) pairs of run times where a's is smaller, with ties
+/// counting half (the common-language effect size). NaN if either sample is
+/// empty.
+pub fn prob_a_faster(a: &[f32], b: &[f32]) -> f64 {
+    let wins = a
+        .iter()
+        .flat_map(|x| b.iter().map(move |y| match x.total_cmp(y) {
+            Ordering::Less => 1.0,
+            Ordering::Equal => 0.5,
+            Ordering::Greater => 0.0,
+        }))
+        .sum::<f64>();
+    wins / (a.len() * b.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn welch_matches_a_worked_example() {
+        // Example 1 from Wikipedia's "Welch's t-test" article.
+        let a = [27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6, 23.1, 19.6, 19.0, 21.7, 21.4];
+        let b = [27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2, 21.9, 22.1, 22.9, 20.5, 24.4];
+        let (t, df, p) = welch_t_test(&a, &b);
+        assert!((t - -2.4554).abs() < 1e-3, "t = {t}");
+        assert!((df - 24.9885).abs() < 1e-3, "df = {df}");
+        assert!((p - 0.021).abs() < 1e-3, "p = {p}");
+
+        let (t, df, p) = welch_t_test(&[1.0], &b);
+        assert!(t.is_nan() && df.is_nan() && p.is_nan());
+    }
+
+    #[test]
+    fn prob_a_faster_counts_ties_as_half() {
+        // Of the four pairs, 1 beats 2 and 3, 3 loses to 2, and 3 ties 3.
+        assert_eq!(prob_a_faster(&[1.0, 3.0], &[2.0, 3.0]), 0.625);
+        assert_eq!(prob_a_faster(&[2.0, 3.0], &[1.0, 3.0]), 0.375);
+        assert_eq!(prob_a_faster(&[1.0], &[5.0, 6.0, 7.0]), 1.0);
+        assert!(prob_a_faster(&[], &[1.0]).is_nan());
+    }
+
+    #[test]
+    fn sample_size_matches_the_textbook_formula() {
+        // Both variances are 1, so n = 2 (1.960 + 0.842)^2 / 1^2 = 15.7.
+        let (a, b) = ([-1.0, 0.0, 1.0], [9.0, 10.0, 11.0]);
+        assert_eq!(required_sample_size(&a, &b, 1.0, 0.95, DEFAULT_POWER), 16.0);
+        // Halving the effect quadruples the runs needed.
+        assert_eq!(required_sample_size(&a, &b, 0.5, 0.95, DEFAULT_POWER), 63.0);
+        assert!(required_sample_size(&a, &[1.0], 1.0, 0.95, DEFAULT_POWER).is_nan());
+    }
+}
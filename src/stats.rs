@@ -0,0 +1,915 @@
+use std::collections::BTreeMap;
+
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use statrs::distribution::{ContinuousCDF, Normal};
+use tracing::warn;
+
+use crate::{intervals, Entry, Line};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Result {
+    pub num_runs: usize,
+    pub num_successes: usize,
+    pub run_times: Vec<f32>,
+    /// Whether any run time was written with a unit suffix (and so converted).
+    pub units_normalized: bool,
+    /// The final fitness of every `SUCCESS` entry that had one.
+    pub final_fitnesses: Vec<f32>,
+    /// The run times of the runs (matched by path) that also logged a
+    /// `SUCCESS`.
+    pub success_times: Vec<f32>,
+    /// The runs that logged a time but never a `SUCCESS`.
+    pub timed_no_success: usize,
+}
+
+/// Run times are compared with the default `approx_eq` tolerances, so that
+/// values which only differ by float formatting still count as equal.
+impl PartialEq for Result {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_runs == other.num_runs
+            && self.num_successes == other.num_successes
+            && self.timed_no_success == other.timed_no_success
+            && self.units_normalized == other.units_normalized
+            && all_approx_eq(&self.run_times, &other.run_times)
+            && all_approx_eq(&self.final_fitnesses, &other.final_fitnesses)
+            && all_approx_eq(&self.success_times, &other.success_times)
+    }
+}
+
+/// Whether `a` and `b` have the same length and are pairwise `approx_eq` at
+/// the default tolerances.
+fn all_approx_eq(a: &[f32], b: &[f32]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(&a, &b)| approx_eq(a, b, DEFAULT_RELATIVE_ERROR, DEFAULT_ABSOLUTE_ERROR))
+}
+
+/// The default relative tolerance for `approx_eq`.
+pub const DEFAULT_RELATIVE_ERROR: f32 = 1e-6;
+
+/// The default absolute tolerance for `approx_eq`, for values near 0 where
+/// a relative tolerance is too strict.
+pub const DEFAULT_ABSOLUTE_ERROR: f32 = 1e-9;
+
+/// Whether `a` and `b` are within `abs` of each other, or within `rel`
+/// times the larger of their magnitudes. Equal infinities are equal; NaN
+/// isn't equal to anything.
+pub fn approx_eq(a: f32, b: f32, rel: f32, abs: f32) -> bool {
+    // The relative tolerance of an infinity is infinite, so only finite values
+    // get one.
+    a == b
+        || (a.is_finite() && b.is_finite() && (a - b).abs() <= abs.max(rel * a.abs().max(b.abs())))
+}
+
+pub type Data = BTreeMap<(u32, u32), Result>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Stat {
+    pub result: Result,
+    pub mean_run_time: f32,
+    pub median_run_time: f32,
+    pub min_run_time: f32,
+    pub max_run_time: f32,
+    pub q1_run_time: f32,
+    pub q3_run_time: f32,
+    pub std_dev_run_time: f32,
+    pub sem_run_time: f32,
+    /// `mean_run_time + risk_lambda * std_dev_run_time`, for preferring
+    /// configurations that are consistently fast.
+    pub risk_adjusted_time: f32,
+    /// Confidence interval around `mean_run_time`, using `--ci-method`.
+    pub mean_ci_low: Option<f32>,
+    pub mean_ci_high: Option<f32>,
+    pub successes_per_mean: f32,
+    pub successes_per_median: f32,
+    /// `sum(run_times) / num_successes`, the expected time spent per
+    /// success; `None` when nothing succeeded or there are no run times.
+    pub effort_per_success: Option<f32>,
+    /// The mean final fitness over the successes that reported one.
+    pub mean_final_fitness: Option<f32>,
+    /// The normalized area under the fraction-of-runs-succeeded-by-time
+    /// curve; see `success_time_auc`.
+    pub auc_success_time: Option<f32>,
+    /// `num_successes / num_runs`; `None` without any runs, even if there
+    /// are `SUCCESS` entries.
+    pub success_rate: Option<f32>,
+    /// `median_run_time / mean_run_time`; well below 1 suggests a long tail of
+    /// slow runs. `None` when the mean is 0 (or undefined).
+    pub median_mean_ratio: Option<f32>,
+    /// Wilson score interval around `success_rate`; `None` without any runs.
+    pub success_rate_ci_low: Option<f32>,
+    pub success_rate_ci_high: Option<f32>,
+    /// `mean_run_time` divided by the smallest `mean_run_time` across all
+    /// configurations; only filled in by `--normalize mean`.
+    pub relative_mean: Option<f32>,
+    /// The value of `--metric-formula`; only filled in when there is one.
+    pub formula: Option<f32>,
+}
+
+pub type Stats = BTreeMap<(u32, u32), Stat>;
+
+/// The entries of `stats` sorted by key. `Stats` is a `BTreeMap`, so this is
+/// its iteration order, but all output goes through here so that the order
+/// stays the same from one run to the next even if the map type changes.
+pub fn sorted_entries(stats: &Stats) -> Vec<(&(u32, u32), &Stat)> {
+    stats.iter().collect()
+}
+
+impl<'a, 'b> FromIterator<&'a Line<'b>> for Data {
+    fn from_iter<T: IntoIterator<Item = &'a Line<'b>>>(iter: T) -> Self {
+        let mut data = Self::new();
+        // Whether each run has logged its time and a success yet, to link the
+        // two. Runs are told apart by path, like in `OutcomeCheck`, so merged
+        // `--relaxed-paths` trials with the same run number stay separate.
+        let mut runs = BTreeMap::<_, (Option<f32>, bool)>::new();
+        for line in iter {
+            let key = (line.population_size, line.num_generations);
+            let run = runs.entry((key, line.path)).or_default();
+            match line.entry {
+                Entry::Success(_) => run.1 = true,
+                Entry::RunTime(value, _) => run.0 = Some(value),
+            }
+            let result = data.entry(key).or_default();
+            match line.entry {
+                Entry::Success(details) => {
+                    result.num_successes += 1;
+                    result.final_fitnesses.extend(details.map(|d| d.fitness));
+                },
+                Entry::RunTime(value, unit) => {
+                    result.num_runs += 1;
+                    result.run_times.push(value);
+                    result.units_normalized |= unit.is_some();
+                },
+            }
+        }
+        for ((key, _), run) in runs {
+            let result = data.entry(key).or_default();
+            match run {
+                (Some(run_time), true) => result.success_times.push(run_time),
+                (Some(_), false) => result.timed_no_success += 1,
+                (None, _) => {}
+            }
+        }
+        data
+    }
+}
+
+// All of the helpers below agree on the single-sample case: the mean,
+// median, min, max, and every percentile are that sample, and the variance,
+// standard deviation, and standard error are 0. An empty slice gives NaN
+// rather than panicking.
+
+/// Which value an even number of samples uses as the median.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MedianMethod {
+    /// The mean of the two middle values
+    #[default]
+    Average,
+    /// The smaller of the two middle values
+    Lower,
+    /// The larger of the two middle values
+    Higher,
+}
+
+pub fn median(vals: &mut[f32], method: MedianMethod) -> f32 {
+    vals.sort_unstable_by(f32::total_cmp);
+    let n = vals.len();
+    match method {
+        _ if n % 2 == 1 || n == 0 => percentile(vals, 0.5),
+        MedianMethod::Average => percentile(vals, 0.5),
+        MedianMethod::Lower => vals[n / 2 - 1],
+        MedianMethod::Higher => vals[n / 2],
+    }
+}
+
+pub fn mean(vals: &[f32]) -> f32 {
+    vals.iter().sum::<f32>() / vals.len() as f32
+}
+
+pub fn min(vals: &[f32]) -> f32 {
+    vals.iter().copied().min_by(f32::total_cmp).unwrap_or(f32::NAN)
+}
+
+pub fn max(vals: &[f32]) -> f32 {
+    vals.iter().copied().max_by(f32::total_cmp).unwrap_or(f32::NAN)
+}
+
+/// The sample (n - 1) variance.
+pub fn variance(vals: &[f32]) -> f32 {
+    match vals.len() {
+        0 => f32::NAN,
+        1 => 0.0,
+        n => {
+            let mean = mean(vals);
+            vals.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / (n - 1) as f32
+        }
+    }
+}
+
+pub fn std_dev(vals: &[f32]) -> f32 {
+    variance(vals).sqrt()
+}
+
+/// The standard error of the mean.
+pub fn sem(vals: &[f32]) -> f32 {
+    std_dev(vals) / (vals.len() as f32).sqrt()
+}
+
+/// The `p`th quantile (0.0..=1.0) of already sorted values, linearly
+/// interpolating between the two closest ranks.
+pub fn percentile(sorted: &[f32], p: f32) -> f32 {
+    if sorted.is_empty() {
+        return f32::NAN;
+    }
+    let rank = p * (sorted.len() - 1) as f32;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (rank - lower as f32) * (sorted[upper] - sorted[lower])
+}
+
+/// Counts of the finite `vals` in `bins` equal-width bins from their min
+/// to their max. When every value is the same they all land in the first
+/// bin; with no finite values (or no bins) there are no counts.
+pub fn histogram(vals: &[f32], bins: usize) -> Vec<u64> {
+    let finite = vals.iter().copied().filter(|v| v.is_finite()).collect::<Vec<_>>();
+    if finite.is_empty() || bins == 0 {
+        return Vec::new();
+    }
+    let low = min(&finite);
+    let width = (max(&finite) - low) / bins as f32;
+    let mut counts = vec![0; bins];
+    for v in finite {
+        let bin = if width > 0.0 { ((v - low) / width) as usize } else { 0 };
+        counts[bin.min(bins - 1)] += 1;
+    }
+    counts
+}
+
+/// The run times outside Tukey's fences, more than 1.5 interquartile ranges
+/// below the first quartile or above the third, in ascending order.
+pub fn outliers(vals: &[f32]) -> Vec<f32> {
+    let mut sorted = vals.to_vec();
+    sorted.sort_unstable_by(f32::total_cmp);
+    let (q1, q3) = (percentile(&sorted, 0.25), percentile(&sorted, 0.75));
+    let reach = 1.5 * (q3 - q1);
+    sorted.retain(|&v| v < q1 - reach || v > q3 + reach);
+    sorted
+}
+
+/// The Wilson score interval for a binomial proportion, where `z` is the
+/// standard normal critical value for the desired confidence (1.96 for 95%).
+/// Returns `None` when there are no trials.
+pub fn wilson_interval(successes: usize, total: usize, z: f64) -> Option<(f64, f64)> {
+    if total == 0 {
+        return None;
+    }
+    let n = total as f64;
+    let p = successes as f64 / n;
+    let z2 = z * z;
+    let denominator = 1.0 + z2 / n;
+    let center = (p + z2 / (2.0 * n)) / denominator;
+    let half_width = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+    Some((center - half_width, center + half_width))
+}
+
+/// The two-sided standard normal critical value for `confidence`, e.g.
+/// about 1.96 for 0.95.
+pub fn z_for_confidence(confidence: f64) -> f64 {
+    let normal = Normal::new(0.0, 1.0).expect("the standard normal is valid");
+    normal.inverse_cdf(1.0 - (1.0 - confidence) / 2.0)
+}
+
+/// The area under the curve of the fraction of all `num_runs` runs that had
+/// succeeded by each time, from 0 to `horizon` (the slowest run), divided by
+/// `horizon` so it lies between 0 and 1. Integrated with the trapezoidal rule
+/// between the sorted `success_times`, starting from (0, 0) and holding the
+/// final fraction out to `horizon`. Faster and more reliable configurations
+/// score higher. `None` without any runs or when `horizon` isn't positive.
+pub fn success_time_auc(success_times: &[f32], num_runs: usize, horizon: f32) -> Option<f32> {
+    if num_runs == 0 || horizon.is_nan() || horizon <= 0.0 {
+        return None;
+    }
+    let mut times = success_times.to_vec();
+    times.sort_unstable_by(f32::total_cmp);
+    let (mut area, mut last_time, mut last_fraction) = (0.0, 0.0, 0.0);
+    for (i, &time) in times.iter().enumerate() {
+        let fraction = (i + 1) as f32 / num_runs as f32;
+        area += (time - last_time) * (last_fraction + fraction) / 2.0;
+        (last_time, last_fraction) = (time, fraction);
+    }
+    area += (horizon - last_time) * last_fraction;
+    Some(area / horizon)
+}
+
+/// `mean + lambda * std_dev`. A lambda of 0 is just the mean even when the
+/// standard deviation is NaN, as it is without any run times.
+pub fn risk_adjusted_time(mean: f32, std_dev: f32, lambda: f32) -> f32 {
+    if lambda == 0.0 {
+        mean
+    } else {
+        mean + lambda * std_dev
+    }
+}
+
+/// Settings that affect how stats are computed from the aggregated data.
+#[derive(Debug, Clone, Copy)]
+pub struct StatsOptions {
+    /// Confidence level (between 0 and 1) for the intervals.
+    pub confidence: f64,
+    /// How to compute the interval around the mean, if at all.
+    pub ci_method: Option<intervals::CiMethod>,
+    /// Number of resamples for the bootstrap interval.
+    pub resamples: usize,
+    /// Seed for the bootstrap; a random one is picked when this is `None`.
+    pub seed: Option<u64>,
+    pub median_method: MedianMethod,
+    /// How heavily `risk_adjusted_time` weighs the standard deviation.
+    pub risk_lambda: f32,
+}
+
+impl StatsOptions {
+    /// The seed to base every configuration's random number generator on.
+    pub fn base_seed(&self) -> u64 {
+        self.seed.unwrap_or_else(rand::random)
+    }
+}
+
+/// A seed derived from the contents of `data`, so the same input always
+/// gives the same draws. This is a 64-bit FNV-1a hash over every key, count,
+/// and run time in key order, which (unlike `DefaultHasher`) doesn't change
+/// between Rust releases or platforms.
+pub fn data_seed(data: &Data) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(PRIME);
+        }
+    };
+    for (&(pop_size, num_gens), result) in data {
+        feed(&pop_size.to_le_bytes());
+        feed(&num_gens.to_le_bytes());
+        feed(&(result.num_runs as u64).to_le_bytes());
+        feed(&(result.num_successes as u64).to_le_bytes());
+        for run_time in &result.run_times {
+            feed(&run_time.to_bits().to_le_bytes());
+        }
+    }
+    hash
+}
+
+/// A generator for one configuration, so that the draws for each
+/// configuration don't depend on the (arbitrary) order they're visited in.
+pub fn config_rng(base_seed: u64, (pop_size, num_gens): (u32, u32)) -> StdRng {
+    StdRng::seed_from_u64(base_seed ^ (u64::from(pop_size) << 32 | u64::from(num_gens)))
+}
+
+pub fn data_to_stats(data: Data, options: &StatsOptions) -> Stats {
+    let base_seed = options.base_seed();
+    let mut stats = Stats::new();
+    for (key, result) in data {
+        if result.num_runs == 0 && result.num_successes > 0 {
+            warn!("configuration {}:{} has SUCCESS entries but no run times, so no success rate or effort per success", key.0, key.1);
+        }
+        let (mean_run_time, variance) = (mean(&result.run_times), variance(&result.run_times));
+        stats.insert(key, result_to_stat(key, result, mean_run_time, variance, options, base_seed));
+    }
+    stats
+}
+
+/// Computes a configuration's `Stat`. The mean and sample variance of the
+/// run times are passed in so callers that keep running moments (like
+/// `Accumulator`) don't have to recompute them; only the order statistics
+/// and confidence intervals go back to the run times themselves.
+pub fn result_to_stat(
+    key: (u32, u32),
+    result: Result,
+    mean_run_time: f32,
+    variance: f32,
+    options: &StatsOptions,
+    base_seed: u64,
+) -> Stat {
+    let z = z_for_confidence(options.confidence);
+    let successes = result.num_successes as f32;
+    let success_rate_ci = wilson_interval(result.num_successes, result.num_runs, z);
+    let mut run_times = result.run_times.clone();
+    let median_run_time = median(&mut run_times, options.median_method);
+    let std_dev_run_time = variance.sqrt();
+    let mean_ci = match options.ci_method {
+        Some(intervals::CiMethod::T) => intervals::t_confidence_interval(&run_times, options.confidence),
+        Some(intervals::CiMethod::Bootstrap) => {
+            let mut rng = config_rng(base_seed, key);
+            intervals::bootstrap_interval(&run_times, options.confidence, options.resamples, &mut rng)
+        }
+        None => None,
+    };
+    Stat {
+        mean_run_time,
+        median_run_time,
+        min_run_time: min(&run_times),
+        max_run_time: max(&run_times),
+        q1_run_time: percentile(&run_times, 0.25),
+        q3_run_time: percentile(&run_times, 0.75),
+        std_dev_run_time,
+        risk_adjusted_time: risk_adjusted_time(mean_run_time, std_dev_run_time, options.risk_lambda),
+        sem_run_time: std_dev_run_time / (run_times.len() as f32).sqrt(),
+        mean_ci_low: mean_ci.map(|(low, _)| low),
+        mean_ci_high: mean_ci.map(|(_, high)| high),
+        successes_per_mean: successes / mean_run_time,
+        successes_per_median: successes / median_run_time,
+        effort_per_success: (result.num_successes > 0 && result.num_runs > 0).then(|| result.run_times.iter().sum::<f32>() / successes),
+        median_mean_ratio: (mean_run_time != 0.0 && mean_run_time.is_finite()).then(|| median_run_time / mean_run_time),
+        mean_final_fitness: (!result.final_fitnesses.is_empty()).then(|| mean(&result.final_fitnesses)),
+        auc_success_time: success_time_auc(&result.success_times, result.num_runs, max(&run_times)),
+        success_rate: (result.num_runs > 0).then(|| successes / result.num_runs as f32),
+        success_rate_ci_low: success_rate_ci.map(|(low, _)| low as f32),
+        success_rate_ci_high: success_rate_ci.map(|(_, high)| high as f32),
+        relative_mean: None,
+        formula: None,
+        result,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// Pool all generation counts for each population size
+    Pop,
+    /// Pool all population sizes for each generation count
+    Gens,
+}
+
+/// Re-aggregates `data` with the other axis collapsed to 0, pooling the
+/// counts and run times of every configuration that lands on the same key.
+pub fn group_data(data: Data, group_by: GroupBy) -> Data {
+    pool_data(data, |(pop_size, num_gens)| match group_by {
+        GroupBy::Pop => (pop_size, 0),
+        GroupBy::Gens => (0, num_gens),
+    })
+}
+
+/// Re-aggregates `data` with the generations bucketed into bins of `width`
+/// (0 to width - 1, width to 2 * width - 1, ...), each keyed by its lower
+/// bound.
+pub fn bin_gens(data: Data, width: u32) -> Data {
+    pool_data(data, |(pop_size, num_gens)| (pop_size, num_gens / width * width))
+}
+
+/// Moves every configuration of `data` to `rekey(key)`, pooling the ones
+/// that land on the same key.
+fn pool_data(data: Data, rekey: impl Fn((u32, u32)) -> (u32, u32)) -> Data {
+    let mut grouped = Data::new();
+    for (key, result) in data {
+        let pooled = grouped.entry(rekey(key)).or_default();
+        pooled.num_runs += result.num_runs;
+        pooled.num_successes += result.num_successes;
+        pooled.run_times.extend(result.run_times);
+        pooled.units_normalized |= result.units_normalized;
+        pooled.final_fitnesses.extend(result.final_fitnesses);
+        pooled.success_times.extend(result.success_times);
+        pooled.timed_no_success += result.timed_no_success;
+    }
+    grouped
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Normalize {
+    /// Express each mean run time relative to the fastest configuration's
+    Mean,
+}
+
+/// Fills in `relative_mean` for every configuration, so the configuration
+/// with the fastest mean run time gets 1.0 and the rest are multiples of it.
+pub fn normalize_means(stats: &mut Stats) {
+    let fastest = stats
+        .values()
+        .map(|s| s.mean_run_time)
+        .filter(|m| m.is_finite())
+        .min_by(f32::total_cmp);
+    for s in stats.values_mut() {
+        s.relative_mean = fastest.map(|fastest| s.mean_run_time / fastest);
+    }
+}
+
+/// Counts and run time summed over every configuration, describing the
+/// experiment as a whole.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Totals {
+    pub num_runs: usize,
+    pub num_successes: usize,
+    /// The sum of every run time.
+    pub total_run_time: f32,
+}
+
+impl Totals {
+    /// `num_successes / num_runs` across every configuration; `None`
+    /// without any runs.
+    pub fn success_rate(&self) -> Option<f32> {
+        (self.num_runs > 0).then(|| self.num_successes as f32 / self.num_runs as f32)
+    }
+}
+
+/// A least-squares line `y = slope * x + intercept`, and how much of the
+/// variation in y it explains.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    /// The coefficient of determination; NaN when every y is the same.
+    pub r_squared: f64,
+}
+
+/// The simple linear regression of the `(x, y)` points, or `None` without
+/// at least two distinct x values.
+pub fn linear_fit(points: &[(f64, f64)]) -> Option<LinearFit> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|&(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / n;
+    let sxx = points.iter().map(|&(x, _)| (x - mean_x).powi(2)).sum::<f64>();
+    if points.len() < 2 || sxx == 0.0 {
+        return None;
+    }
+    let sxy = points.iter().map(|&(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>();
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let ss_res = points.iter().map(|&(x, y)| (y - (slope * x + intercept)).powi(2)).sum::<f64>();
+    let ss_tot = points.iter().map(|&(_, y)| (y - mean_y).powi(2)).sum::<f64>();
+    let r_squared = if ss_tot == 0.0 { f64::NAN } else { 1.0 - ss_res / ss_tot };
+    Some(LinearFit { slope, intercept, r_squared })
+}
+
+/// Fits `mean_run_time` against `num_generations` over the configurations
+/// with population size `pop_size`, skipping those whose mean isn't finite.
+pub fn fit_gens(stats: &Stats, pop_size: u32) -> Option<LinearFit> {
+    let points = stats
+        .iter()
+        .filter(|((pop, _), s)| *pop == pop_size && s.mean_run_time.is_finite())
+        .map(|(&(_, num_gens), s)| (f64::from(num_gens), f64::from(s.mean_run_time)))
+        .collect::<Vec<_>>();
+    linear_fit(&points)
+}
+
+pub fn totals(stats: &Stats) -> Totals {
+    stats.values().fold(Totals::default(), |totals, s| Totals {
+        num_runs: totals.num_runs + s.result.num_runs,
+        num_successes: totals.num_successes + s.result.num_successes,
+        total_run_time: totals.total_run_time + s.result.run_times.iter().sum::<f32>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> StatsOptions {
+        StatsOptions {
+            confidence: 0.95,
+            ci_method: None,
+            resamples: 1000,
+            seed: Some(0),
+            median_method: MedianMethod::Average,
+            risk_lambda: 1.0,
+        }
+    }
+
+    fn result(run_times: &[f32], num_successes: usize) -> Result {
+        Result {
+            num_runs: run_times.len(),
+            num_successes,
+            run_times: run_times.to_vec(),
+            ..Result::default()
+        }
+    }
+
+    fn stats(configs: &[((u32, u32), &[f32])]) -> Stats {
+        let data = configs.iter().map(|&(key, run_times)| (key, result(run_times, 0))).collect();
+        data_to_stats(data, &options())
+    }
+
+    #[test]
+    fn sorted_entries_are_in_key_order_every_time() {
+        let stats = stats(&[((200, 10), &[1.0]), ((100, 50), &[2.0]), ((100, 10), &[3.0])]);
+        let keys = || sorted_entries(&stats).into_iter().map(|(&key, _)| key).collect::<Vec<_>>();
+        assert_eq!(keys(), [(100, 10), (100, 50), (200, 10)]);
+        assert_eq!(keys(), keys());
+    }
+
+    #[test]
+    fn normalize_means_is_relative_to_the_fastest() {
+        let mut stats = stats(&[((100, 50), &[20.0, 25.0]), ((200, 50), &[30.0, 30.0]), ((300, 50), &[15.0])]);
+        normalize_means(&mut stats);
+        assert_eq!(stats[&(300, 50)].relative_mean, Some(1.0));
+        assert_eq!(stats[&(100, 50)].relative_mean, Some(1.5));
+        assert_eq!(stats[&(200, 50)].relative_mean, Some(2.0));
+    }
+
+    #[test]
+    fn a_single_run_time_is_every_location_stat_with_no_spread() {
+        let options = StatsOptions {
+            ci_method: Some(intervals::CiMethod::Bootstrap),
+            ..options()
+        };
+        let stat = result_to_stat((100, 50), result(&[7.5], 1), 7.5, 0.0, &options, 0);
+        for value in [
+            stat.mean_run_time,
+            stat.median_run_time,
+            stat.min_run_time,
+            stat.max_run_time,
+            stat.q1_run_time,
+            stat.q3_run_time,
+        ] {
+            assert_eq!(value, 7.5);
+        }
+        assert_eq!(stat.std_dev_run_time, 0.0);
+        assert_eq!(stat.sem_run_time, 0.0);
+        assert_eq!(stat.mean_ci_low, Some(7.5));
+        assert_eq!(stat.mean_ci_high, Some(7.5));
+
+        assert_eq!(variance(&[7.5]), 0.0);
+        for p in [0.0, 0.1, 0.5, 0.9, 1.0] {
+            assert_eq!(percentile(&[7.5], p), 7.5);
+        }
+        assert_eq!(intervals::t_confidence_interval(&[7.5], 0.95), None);
+    }
+
+    #[test]
+    fn grouping_pools_every_configuration_on_the_other_axis() {
+        let data = || Data::from([((100, 50), result(&[1.0, 2.0], 1)), ((100, 60), result(&[3.0], 1)), ((200, 50), result(&[4.0], 0))]);
+
+        let by_pop = group_data(data(), GroupBy::Pop);
+        assert_eq!(by_pop.keys().copied().collect::<Vec<_>>(), [(100, 0), (200, 0)]);
+        assert_eq!(by_pop[&(100, 0)], result(&[1.0, 2.0, 3.0], 2));
+        assert_eq!(by_pop[&(200, 0)], result(&[4.0], 0));
+
+        let by_gens = group_data(data(), GroupBy::Gens);
+        assert_eq!(by_gens.keys().copied().collect::<Vec<_>>(), [(0, 50), (0, 60)]);
+        assert_eq!(by_gens[&(0, 50)], result(&[1.0, 2.0, 4.0], 1));
+        assert_eq!(by_gens[&(0, 60)], result(&[3.0], 1));
+    }
+
+    #[test]
+    fn wilson_interval_for_eight_of_ten() {
+        let z = z_for_confidence(0.95);
+        assert!((z - 1.96).abs() < 1e-3);
+        let (low, high) = wilson_interval(8, 10, z).unwrap();
+        assert!((low - 0.4902).abs() < 1e-4, "low = {low}");
+        assert!((high - 0.9433).abs() < 1e-4, "high = {high}");
+
+        assert_eq!(wilson_interval(0, 0, z), None);
+        let (low, high) = wilson_interval(0, 10, z).unwrap();
+        assert!(low.abs() < 1e-12 && high > 0.0);
+    }
+
+    #[test]
+    fn median_mean_ratio_flags_right_skew() {
+        let stats = stats(&[((100, 50), &[6.0, 8.0, 16.0]), ((200, 50), &[0.0, 0.0]), ((300, 50), &[])]);
+        assert_eq!(stats[&(100, 50)].median_mean_ratio, Some(0.8));
+        assert_eq!(stats[&(200, 50)].median_mean_ratio, None);
+        assert_eq!(stats[&(300, 50)].median_mean_ratio, None);
+    }
+
+    fn data(text: &str) -> Data {
+        let (lines, _) = crate::parse_lines(text, &crate::ParseOptions::default()).unwrap();
+        lines.iter().collect()
+    }
+
+    #[test]
+    fn only_configurations_with_suffixed_times_are_unit_normalized() {
+        let data = data(
+            "\
+PS_100/NG_50/run_1.output: 250ms
+PS_100/NG_50/run_2.output: 1.5
+PS_200/NG_50/run_1.output: 2
+PS_300/NG_50/run_1.output: 2min
+",
+        );
+        assert_eq!(data[&(100, 50)].run_times, [0.25, 1.5]);
+        assert_eq!(data[&(300, 50)].run_times, [120.0]);
+        let flags = [(100, 50), (200, 50), (300, 50)].map(|key| data[&key].units_normalized);
+        assert_eq!(flags, [true, false, true]);
+    }
+
+    #[test]
+    fn configurations_iterate_in_key_order() {
+        let data = data(
+            "\
+PS_500/NG_10/run_1.output: 1
+PS_100/NG_200/run_1.output: 1
+PS_100/NG_50/run_1.output: 1
+PS_20/NG_999/run_1.output: 1
+",
+        );
+        let expected = [(20, 999), (100, 50), (100, 200), (500, 10)];
+        assert_eq!(data.keys().copied().collect::<Vec<_>>(), expected);
+        assert_eq!(data_to_stats(data, &options()).keys().copied().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn approx_eq_at_the_default_tolerances() {
+        let eq = |a, b| approx_eq(a, b, DEFAULT_RELATIVE_ERROR, DEFAULT_ABSOLUTE_ERROR);
+        assert!(eq(1.0, 1.0));
+        assert!(eq(0.1 + 0.2, 0.3));
+        assert!(eq(1e6, 1e6 + 0.5));
+        assert!(eq(0.0, 1e-10));
+        assert!(eq(f32::INFINITY, f32::INFINITY));
+
+        assert!(!eq(1.0, 1.001));
+        assert!(!eq(1e6, 1e6 + 10.0));
+        assert!(!eq(0.0, 1e-8));
+        assert!(!eq(f32::NAN, f32::NAN));
+        assert!(!eq(f32::INFINITY, f32::NEG_INFINITY));
+        assert!(!eq(f32::INFINITY, 1.0));
+
+        // Result equality goes through the same tolerances.
+        assert_eq!(result(&[0.1 + 0.2], 1), result(&[0.3], 1));
+        assert_ne!(result(&[1.0], 1), result(&[1.001], 1));
+    }
+
+    #[test]
+    fn seeding_from_the_input_repeats_the_bootstrap() {
+        let text = "\
+PS_100/NG_50/run_1.output: 4.5
+PS_100/NG_50/run_2.output: 6.0
+PS_100/NG_50/run_3.output: 1.25
+PS_200/NG_50/run_1.output: 3.0
+PS_200/NG_50/run_2.output: 9.5
+";
+        let intervals = || {
+            let data = data(text);
+            let options = StatsOptions {
+                ci_method: Some(intervals::CiMethod::Bootstrap),
+                seed: Some(data_seed(&data)),
+                ..options()
+            };
+            data_to_stats(data, &options)
+                .values()
+                .map(|s| (s.mean_ci_low.unwrap(), s.mean_ci_high.unwrap()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(intervals(), intervals());
+
+        assert_eq!(data_seed(&data(text)), data_seed(&data(text)));
+        assert_ne!(data_seed(&data(text)), data_seed(&data("PS_100/NG_50/run_1.output: 4.5\n")));
+    }
+
+    #[test]
+    fn mean_final_fitness_averages_the_successes_that_report_one() {
+        let data = data(
+            "\
+PS_100/NG_50/run_1.output: 4.5
+PS_100/NG_50/run_1.output:SUCCESS 27 0.5
+PS_100/NG_50/run_2.output: 6.0
+PS_100/NG_50/run_2.output:SUCCESS 31 1.5
+PS_100/NG_50/run_3.output:SUCCESS
+PS_200/NG_50/run_1.output: 3.0
+PS_200/NG_50/run_1.output:SUCCESS
+",
+        );
+        assert_eq!(data[&(100, 50)].final_fitnesses, [0.5, 1.5]);
+        assert_eq!(data[&(100, 50)].num_successes, 3);
+        let stats = data_to_stats(data, &options());
+        assert_eq!(stats[&(100, 50)].mean_final_fitness, Some(1.0));
+        assert_eq!(stats[&(200, 50)].mean_final_fitness, None);
+    }
+
+    #[test]
+    fn median_methods_on_four_values() {
+        let median_of = |method| median(&mut [7.0, 1.0, 4.0, 2.0], method);
+        assert_eq!(median_of(MedianMethod::Average), 3.0);
+        assert_eq!(median_of(MedianMethod::Lower), 2.0);
+        assert_eq!(median_of(MedianMethod::Higher), 4.0);
+
+        // An odd count has a single middle value whatever the method.
+        for method in [MedianMethod::Average, MedianMethod::Lower, MedianMethod::Higher] {
+            assert_eq!(median(&mut [3.0, 9.0, 1.0], method), 3.0);
+        }
+    }
+
+    #[test]
+    fn totals_sum_over_every_configuration() {
+        let data = Data::from([((100, 50), result(&[1.5, 2.5, 4.0], 2)), ((200, 50), result(&[2.0], 0))]);
+        let totals = totals(&data_to_stats(data, &options()));
+        assert_eq!(
+            totals,
+            Totals {
+                num_runs: 4,
+                num_successes: 2,
+                total_run_time: 10.0,
+            }
+        );
+        assert_eq!(totals.success_rate(), Some(0.5));
+        assert_eq!(Totals::default().success_rate(), None);
+    }
+
+    #[test]
+    fn gens_bins_are_keyed_by_their_lower_bound() {
+        let data = Data::from([
+            ((100, 40), result(&[1.0], 1)),
+            ((100, 55), result(&[2.0], 0)),
+            ((100, 60), result(&[3.0], 1)),
+            ((200, 60), result(&[4.0], 1)),
+        ]);
+        let binned = bin_gens(data, 50);
+        assert_eq!(binned.keys().copied().collect::<Vec<_>>(), [(100, 0), (100, 50), (200, 50)]);
+        assert_eq!(binned[&(100, 0)], result(&[1.0], 1));
+        assert_eq!(binned[&(100, 50)], result(&[2.0, 3.0], 1));
+    }
+
+    #[test]
+    fn success_time_auc_integrates_the_trapezoids() {
+        // A quarter of the runs have succeeded by 2 and half by 4; the
+        // trapezoids up to 4 have areas 0.25 and 0.75, and holding 0.5 out to
+        // the horizon of 8 adds 2.
+        assert_eq!(success_time_auc(&[4.0, 2.0], 4, 8.0), Some(3.0 / 8.0));
+        assert_eq!(success_time_auc(&[], 4, 8.0), Some(0.0));
+        assert_eq!(success_time_auc(&[1.0], 0, 8.0), None);
+        assert_eq!(success_time_auc(&[1.0], 1, f32::NAN), None);
+    }
+
+    #[test]
+    fn a_perfectly_linear_set_fits_exactly() {
+        let fit = linear_fit(&[(10.0, 7.0), (20.0, 12.0), (40.0, 22.0), (50.0, 27.0)]).unwrap();
+        assert!((fit.slope - 0.5).abs() < 1e-12);
+        assert!((fit.intercept - 2.0).abs() < 1e-12);
+        assert!((fit.r_squared - 1.0).abs() < 1e-12);
+
+        assert!(linear_fit(&[(1.0, 1.0), (1.0, 2.0)]).is_none());
+        assert!(linear_fit(&[(1.0, 3.0), (2.0, 3.0)]).unwrap().r_squared.is_nan());
+
+        let stats = stats(&[((100, 10), &[7.0]), ((100, 20), &[12.0]), ((100, 40), &[22.0]), ((200, 10), &[1.0])]);
+        let fit = fit_gens(&stats, 100).unwrap();
+        assert!((fit.slope - 0.5).abs() < 1e-6 && (fit.r_squared - 1.0).abs() < 1e-6);
+        assert!(fit_gens(&stats, 200).is_none());
+    }
+
+    #[test]
+    fn risk_lambda_penalizes_the_spread() {
+        let data = || Data::from([((100, 50), result(&[5.0, 5.0, 5.0, 5.0], 4)), ((200, 50), result(&[1.0, 9.0, 1.0, 9.0], 4))]);
+        let risk = |risk_lambda| {
+            let stats = data_to_stats(data(), &StatsOptions { risk_lambda, ..options() });
+            (stats[&(100, 50)].risk_adjusted_time, stats[&(200, 50)].risk_adjusted_time)
+        };
+        assert_eq!(risk(0.0), (5.0, 5.0));
+        let (steady, variable) = risk(1.0);
+        assert_eq!(steady, 5.0);
+        // The sample variance of 1, 9, 1, 9 is 64 / 3.
+        assert!((variable - (5.0 + (64.0f32 / 3.0).sqrt())).abs() < 1e-5, "{variable}");
+
+        assert_eq!(risk_adjusted_time(3.0, f32::NAN, 0.0), 3.0);
+        assert_eq!(risk_adjusted_time(3.0, 2.0, 0.5), 4.0);
+    }
+
+    #[test]
+    fn timed_runs_without_a_success_are_counted() {
+        let data = data(
+            "\
+PS_100/NG_50/run_1.output: 4.5
+PS_100/NG_50/run_1.output:SUCCESS
+PS_100/NG_50/run_2.output: 6.0
+PS_100/NG_50/run_3.output:SUCCESS
+PS_100/NG_50/run_4.output: 2.0
+",
+        );
+        assert_eq!(data[&(100, 50)].timed_no_success, 2);
+        assert_eq!(data[&(100, 50)].success_times, [4.5]);
+
+        // Relaxed trials that reuse a run number are separate runs.
+        let relaxed = crate::ParseOptions {
+            relaxed_paths: true,
+            ..crate::ParseOptions::default()
+        };
+        let text = "\
+PS_100/NG_50/trial_1/run_1.output: 4.5
+PS_100/NG_50/trial_1/run_1.output:SUCCESS
+PS_100/NG_50/trial_2/run_1.output: 6.0
+";
+        let (lines, _) = crate::parse_lines(text, &relaxed).unwrap();
+        let data = lines.iter().collect::<Data>();
+        assert_eq!(data[&(100, 50)].timed_no_success, 1);
+        assert_eq!(data[&(100, 50)].success_times, [4.5]);
+    }
+
+    #[test]
+    fn successes_without_runs_have_no_rate() {
+        let data = data(
+            "\
+PS_100/NG_50/run_1.output:SUCCESS
+PS_100/NG_50/run_2.output:SUCCESS
+PS_200/NG_50/run_1.output: 4.5
+PS_200/NG_50/run_1.output:SUCCESS
+",
+        );
+        assert_eq!((data[&(100, 50)].num_runs, data[&(100, 50)].num_successes), (0, 2));
+        let stats = data_to_stats(data, &options());
+
+        let no_runs = &stats[&(100, 50)];
+        assert_eq!(no_runs.success_rate, None);
+        assert_eq!(no_runs.effort_per_success, None);
+        assert_eq!((no_runs.success_rate_ci_low, no_runs.success_rate_ci_high), (None, None));
+        assert!(no_runs.mean_run_time.is_nan());
+
+        assert_eq!(stats[&(200, 50)].success_rate, Some(1.0));
+    }
+}
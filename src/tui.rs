@@ -0,0 +1,183 @@
+use std::{collections::BTreeSet, io};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Cell, Paragraph, Row, Sparkline, Table},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    histogram,
+    output::{format_value, Column, COLUMNS},
+    Stat, Stats,
+};
+
+/// Bars in the selected configuration's run time sparkline.
+const HISTOGRAM_BINS: usize = 20;
+
+/// The state of the `--tui` browser: a grid of `metric` with a row per
+/// population size and a column per generation count, and the selected cell.
+pub struct App<'a> {
+    stats: &'a Stats,
+    metric: &'static Column,
+    na_string: &'a str,
+    pops: Vec<u32>,
+    gens: Vec<u32>,
+    row: usize,
+    column: usize,
+}
+
+impl<'a> App<'a> {
+    pub fn new(stats: &'a Stats, metric: &'static Column, na_string: &'a str) -> Self {
+        let pops = stats.keys().map(|&(pop_size, _)| pop_size).collect::<BTreeSet<_>>();
+        let gens = stats.keys().map(|&(_, num_gens)| num_gens).collect::<BTreeSet<_>>();
+        Self {
+            stats,
+            metric,
+            na_string,
+            pops: pops.into_iter().collect(),
+            gens: gens.into_iter().collect(),
+            row: 0,
+            column: 0,
+        }
+    }
+
+    /// The selected configuration and its stats, if that cell has data.
+    pub fn selected(&self) -> Option<((u32, u32), &'a Stat)> {
+        let key = (*self.pops.get(self.row)?, *self.gens.get(self.column)?);
+        self.stats.get(&key).map(|stat| (key, stat))
+    }
+
+    /// Moves the selection, stopping at the edges of the grid.
+    pub fn move_by(&mut self, rows: isize, columns: isize) {
+        let step = |index: usize, by: isize, len: usize| index.saturating_add_signed(by).min(len.saturating_sub(1));
+        self.row = step(self.row, rows, self.pops.len());
+        self.column = step(self.column, columns, self.gens.len());
+    }
+
+    /// The side panel's `name: value` lines, one per output column, for the
+    /// selected configuration.
+    pub fn detail_lines(&self) -> Vec<String> {
+        match self.selected() {
+            Some((key, stat)) => COLUMNS
+                .iter()
+                .map(|c| format!("{}: {}", c.name, format_value(c.value(&key, stat), self.na_string)))
+                .collect(),
+            None => vec!["no data for this configuration".to_string()],
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [grid_area, side_area] = Layout::horizontal([Constraint::Fill(2), Constraint::Fill(1)]).areas(frame.area());
+        let [details_area, histogram_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(8)]).areas(side_area);
+
+        let header = Row::new(["pop\\gens".to_string()].into_iter().chain(self.gens.iter().map(u32::to_string)))
+            .style(Style::new().add_modifier(Modifier::BOLD));
+        let rows = self.pops.iter().enumerate().map(|(r, &pop_size)| {
+            let cells = self.gens.iter().enumerate().map(|(c, &num_gens)| {
+                let key = (pop_size, num_gens);
+                let text = self.stats.get(&key).map_or(self.na_string.to_string(), |s| {
+                    format_value(self.metric.value(&key, s), self.na_string)
+                });
+                let style = if (r, c) == (self.row, self.column) {
+                    Style::new().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::new()
+                };
+                Cell::from(text).style(style)
+            });
+            Row::new([Cell::from(pop_size.to_string()).style(Style::new().add_modifier(Modifier::BOLD))].into_iter().chain(cells))
+        });
+        let widths = vec![Constraint::Length(12); self.gens.len() + 1];
+        let title = format!(" {} (arrows to move, q to quit) ", self.metric.name);
+        frame.render_widget(Table::new(rows, widths).header(header).block(Block::bordered().title(title)), grid_area);
+
+        let details = self.detail_lines().into_iter().map(Line::from).collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(details).block(Block::bordered().title(" stats ")), details_area);
+
+        let counts = self
+            .selected()
+            .map_or_else(Vec::new, |(_, stat)| histogram(&stat.result.run_times, HISTOGRAM_BINS));
+        let sparkline = Sparkline::default().data(&counts).block(Block::bordered().title(" run times "));
+        frame.render_widget(sparkline, histogram_area);
+    }
+}
+
+/// Takes over the terminal to browse `app` until the user quits.
+pub fn run(app: &mut App) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, app);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| app.render(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => app.move_by(-1, 0),
+            KeyCode::Down | KeyCode::Char('j') => app.move_by(1, 0),
+            KeyCode::Left | KeyCode::Char('h') => app.move_by(0, -1),
+            KeyCode::Right | KeyCode::Char('l') => app.move_by(0, 1),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data_to_stats, output::parse_column, Data, MedianMethod, Result, StatsOptions};
+
+    fn stats() -> Stats {
+        let result = |run_times: &[f32]| Result {
+            num_runs: run_times.len(),
+            num_successes: 1,
+            run_times: run_times.to_vec(),
+            ..Result::default()
+        };
+        let data = Data::from([((100, 50), result(&[2.0, 4.0])), ((100, 100), result(&[5.0])), ((200, 100), result(&[1.0]))]);
+        let options = StatsOptions {
+            confidence: 0.95,
+            ci_method: None,
+            resamples: 1000,
+            seed: Some(0),
+            median_method: MedianMethod::Average,
+            risk_lambda: 1.0,
+        };
+        data_to_stats(data, &options)
+    }
+
+    #[test]
+    fn the_selected_cell_shows_its_stats() {
+        let stats = stats();
+        let mut app = App::new(&stats, parse_column("mean_run_time").unwrap(), "-");
+        assert_eq!(app.selected().map(|(key, _)| key), Some((100, 50)));
+        let details = app.detail_lines();
+        assert_eq!(details.len(), COLUMNS.len());
+        assert!(details.contains(&"mean_run_time: 3".to_string()));
+        assert!(details.contains(&"num_runs: 2".to_string()));
+        assert!(details.contains(&"mean_ci_low: -".to_string()));
+
+        app.move_by(1, 0);
+        assert!(app.selected().is_none());
+        assert_eq!(app.detail_lines(), ["no data for this configuration"]);
+
+        // Moving stops at the edges of the grid.
+        app.move_by(5, 5);
+        assert_eq!(app.selected().map(|(key, _)| key), Some((200, 100)));
+        app.move_by(-5, 0);
+        assert_eq!(app.selected().map(|(key, _)| key), Some((100, 100)));
+    }
+}